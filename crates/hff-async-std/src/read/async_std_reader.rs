@@ -3,10 +3,7 @@ use async_std::{
     io::{prelude::SeekExt, ReadExt},
     sync::{Mutex, MutexGuard},
 };
-use hff_core::{
-    byteorder::ReadBytesExt, ByteOrder, Chunk, ChunkCache, ContentInfo, Ecc, Endian, Error, Header,
-    Result, Table, Version, BE, LE,
-};
+use hff_core::{ByteOrder, Chunk, ChunkCache, ContentInfo, Error, Header, Result, Section, Table};
 use std::mem::size_of;
 
 /// Implements a std reader wrapper around the source.
@@ -34,9 +31,20 @@ impl AsyncStdReader {
         source
             .seek(std::io::SeekFrom::Start(content.offset()))
             .await?;
-        let mut result = vec![0; content.len() as usize];
-        source.read_exact(result.as_mut_slice()).await?;
-        Ok(result)
+        // Let the buffer grow as the data is read rather than trusting
+        // the length for allocation.
+        let mut result = vec![];
+        (&mut **source)
+            .take(content.len())
+            .read_to_end(&mut result)
+            .await?;
+        if result.len() as u64 == content.len() {
+            Ok(result)
+        } else {
+            Err(Error::Truncated {
+                section: Section::Content,
+            })
+        }
     }
 
     /// Get he appropriate reader implementation.
@@ -57,39 +65,22 @@ impl AsyncStdReader {
     ) -> Result<Header> {
         // Read in the entire header ignorant of the endian.
         let mut header = [0_u8; Header::SIZE];
-        reader.read_exact(&mut header).await?;
-
-        // Build a byte reader from the buffer.
-        let reader: &mut dyn std::io::Read = &mut header.as_slice();
-
-        // Read out the magic value to detect endian.
-        let magic = reader.read_u64::<LE>()?;
-
-        // Detect the file content endianess.  NOTE: This only describes
-        // the file structure itself, the chunk content is "not" considered
-        // part of this.  It is up to the user to deal with endianess of
-        // the chunks.
-        match Ecc::HFF_MAGIC.endian(magic.into()) {
-            Some(endian) => match endian {
-                Endian::Little => Ok(Header::with(
-                    magic.into(),
-                    Version::read::<LE>(reader)?,
-                    reader.read_u32::<LE>()?,
-                    Ecc::read::<LE>(reader)?,
-                    reader.read_u32::<LE>()?,
-                    reader.read_u32::<LE>()?,
-                )),
-                Endian::Big => Ok(Header::with(
-                    magic.into(),
-                    Version::read::<BE>(reader)?,
-                    reader.read_u32::<BE>()?,
-                    Ecc::read::<BE>(reader)?,
-                    reader.read_u32::<BE>()?,
-                    reader.read_u32::<BE>()?,
-                )),
-            },
-            None => Err(Error::Invalid("Not an HFF file.".into())),
+        reader
+            .read_exact(&mut header)
+            .await
+            .map_err(|e| Error::from(e).within(Section::Header))?;
+
+        // Detect the file content endianess and parse the header.  NOTE: This
+        // only describes the file structure itself, the chunk content is "not"
+        // considered part of this.  It is up to the user to deal with endianess
+        // of the chunks.
+        let header = Header::try_from(header.as_slice())?;
+
+        // The footer layout is only supported by the hff-std readers.
+        if header.has_footer() {
+            return Err(Error::UnsupportedLayout);
         }
+        Ok(header)
     }
 
     /// Read the tables from the given stream.
@@ -98,9 +89,13 @@ impl AsyncStdReader {
         count: u32,
     ) -> Result<Vec<Table>> {
         if count > 0 {
-            // Create a buffer with appropriate size.
-            let mut buffer = vec![0; count as usize * std::mem::size_of::<Table>()];
-            reader.read_exact(&mut buffer.as_mut_slice()).await?;
+            // Read the table array.
+            let buffer = Self::read_bytes(
+                reader,
+                count as u64 * size_of::<Table>() as u64,
+                Section::Tables,
+            )
+            .await?;
 
             // Read all the tables out of the buffer.
             let mut tables = vec![];
@@ -123,9 +118,13 @@ impl AsyncStdReader {
         count: u32,
     ) -> Result<Vec<Chunk>> {
         if count > 0 {
-            // Create a buffer with the appropriate size.
-            let mut buffer = vec![0; count as usize * std::mem::size_of::<Chunk>()];
-            reader.read_exact(&mut buffer.as_mut_slice()).await?;
+            // Read the chunk array.
+            let buffer = Self::read_bytes(
+                reader,
+                count as u64 * size_of::<Chunk>() as u64,
+                Section::Chunks,
+            )
+            .await?;
 
             // Read the chunks out of the buffer.
             let mut chunks = vec![];
@@ -142,6 +141,22 @@ impl AsyncStdReader {
         }
     }
 
+    /// Read the given number of bytes without trusting the length for
+    /// allocation.  The buffer only grows as data actually arrives.
+    async fn read_bytes(
+        reader: &mut (dyn async_std::io::Read + std::marker::Unpin),
+        length: u64,
+        section: Section,
+    ) -> Result<Vec<u8>> {
+        let mut buffer = vec![];
+        (&mut *reader).take(length).read_to_end(&mut buffer).await?;
+        if buffer.len() as u64 == length {
+            Ok(buffer)
+        } else {
+            Err(Error::Truncated { section })
+        }
+    }
+
     /// Read the body of data from the given stream.  Assumes the stream
     /// has an 'end' to read to.
     pub(super) async fn read_body(
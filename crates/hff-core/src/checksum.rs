@@ -0,0 +1,113 @@
+use crate::Error;
+
+/// The checksum algorithm applied to metadata or chunk content.
+#[repr(u32)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Checksum {
+    /// No checksum is stored.
+    #[default]
+    None = 0,
+    /// CRC-32C (Castagnoli) stored in the low 32 bits.
+    Crc32c = 1,
+    /// XXH64 with a zero seed.
+    XxHash64 = 2,
+}
+
+impl Checksum {
+    /// Compute the checksum of the given data in one shot.
+    pub fn compute(&self, data: &[u8]) -> u64 {
+        let mut hasher = self.hasher();
+        hasher.update(data);
+        hasher.finish()
+    }
+
+    /// Create a streaming hasher for this checksum type.
+    pub fn hasher(&self) -> ChecksumHasher {
+        match self {
+            Self::None => ChecksumHasher::None,
+            Self::Crc32c => ChecksumHasher::Crc32c(0),
+            Self::XxHash64 => ChecksumHasher::XxHash64(Box::new(xxhash_rust::xxh64::Xxh64::new(0))),
+        }
+    }
+}
+
+impl TryFrom<u32> for Checksum {
+    type Error = Error;
+
+    fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Crc32c),
+            2 => Ok(Self::XxHash64),
+            _ => Err(Error::UnknownChecksum(value)),
+        }
+    }
+}
+
+impl From<Checksum> for u32 {
+    fn from(value: Checksum) -> Self {
+        value as u32
+    }
+}
+
+/// Incremental checksum computation for content which is streamed
+/// rather than held in memory.
+pub enum ChecksumHasher {
+    /// Nothing is computed.
+    None,
+    /// Running CRC-32C state.
+    Crc32c(u32),
+    /// Running XXH64 state.
+    XxHash64(Box<xxhash_rust::xxh64::Xxh64>),
+}
+
+impl std::fmt::Debug for ChecksumHasher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "None"),
+            Self::Crc32c(crc) => write!(f, "Crc32c({:08X})", crc),
+            Self::XxHash64(_) => write!(f, "XxHash64"),
+        }
+    }
+}
+
+impl ChecksumHasher {
+    /// Add the data to the checksum.
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::None => {}
+            Self::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+            Self::XxHash64(hasher) => hasher.update(data),
+        }
+    }
+
+    /// Get the final checksum value.
+    pub fn finish(&self) -> u64 {
+        match self {
+            Self::None => 0,
+            Self::Crc32c(crc) => *crc as u64,
+            Self::XxHash64(hasher) => hasher.digest(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streaming() {
+        let data = b"The quick brown fox jumps over the lazy dog";
+        for checksum in [Checksum::Crc32c, Checksum::XxHash64] {
+            let mut hasher = checksum.hasher();
+            for part in data.chunks(7) {
+                hasher.update(part);
+            }
+            assert_eq!(hasher.finish(), checksum.compute(data));
+            assert_ne!(checksum.compute(data), checksum.compute(&data[1..]));
+        }
+        assert_eq!(Checksum::None.compute(data), 0);
+        assert_eq!(Checksum::Crc32c.compute(b"123456789"), 0xE3069283);
+        assert!(Checksum::try_from(3).is_err());
+    }
+}
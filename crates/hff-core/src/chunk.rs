@@ -1,4 +1,4 @@
-use crate::{Identifier, Result};
+use crate::{Error, Identifier, Result};
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use std::{
     fmt::Debug,
@@ -15,14 +15,34 @@ pub struct Chunk {
     length: u64,
     /// Offset of the data from the start of the file.
     offset: u64,
+    /// Checksum of the chunk data.
+    checksum: u64,
+    /// Length of the data once decompressed, zero if not compressed.
+    uncompressed_length: u64,
+    /// The checksum algorithm used, see `Checksum`.
+    checksum_type: u32,
+    /// The compression codec applied to the data, see `Codec`.
+    codec: u32,
+    /// Alignment of the data offset in bytes, a power of two of at least 16.
+    alignment: u32,
+    /// The serde encoding of the data, see `Encoding`.
+    encoding: u32,
 }
 
 impl Debug for Chunk {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "({:X}) - {}, {}",
-            *self.identifier, self.length, self.offset
+            "({:X}) - {}, {}, {}:{:X}, codec: {}:{}, align: {}, enc: {}",
+            *self.identifier,
+            self.length,
+            self.offset,
+            self.checksum_type,
+            self.checksum,
+            self.codec,
+            self.uncompressed_length,
+            self.alignment,
+            self.encoding
         )
     }
 }
@@ -31,12 +51,41 @@ impl Chunk {
     /// Size of the chunk entry.
     pub const SIZE: usize = std::mem::size_of::<Self>();
 
+    /// The default and minimum alignment of content.
+    pub const ALIGNMENT: u32 = 16;
+
+    /// The largest alignment content is written with, anything larger
+    /// would mostly fill the file with padding.
+    pub const MAX_ALIGNMENT: u32 = 1 << 20;
+
+    /// Round the alignment up to a power of two of at least the default.
+    /// Alignments past the largest supported one are an error.
+    pub fn round_alignment(alignment: u32) -> Result<u32> {
+        alignment
+            .checked_next_power_of_two()
+            .filter(|&rounded| rounded <= Self::MAX_ALIGNMENT)
+            .map(|rounded| rounded.max(Self::ALIGNMENT))
+            .ok_or_else(|| {
+                Error::Invalid(format!(
+                    "Alignment {} is larger than the maximum of {}.",
+                    alignment,
+                    Self::MAX_ALIGNMENT
+                ))
+            })
+    }
+
     /// Create a new chunk instance.
     pub fn new(identifier: impl Into<Identifier>, length: u64, offset: u64) -> Self {
         Self {
             identifier: identifier.into(),
             length,
             offset,
+            checksum: 0,
+            uncompressed_length: 0,
+            checksum_type: 0,
+            codec: 0,
+            alignment: Self::ALIGNMENT,
+            encoding: 0,
         }
     }
 
@@ -65,13 +114,81 @@ impl Chunk {
         &mut self.offset
     }
 
+    /// Get the checksum of the content.
+    pub fn checksum(&self) -> u64 {
+        self.checksum
+    }
+
+    /// Get the checksum mutably.
+    pub fn checksum_mut(&mut self) -> &mut u64 {
+        &mut self.checksum
+    }
+
+    /// Get the length of the content once decompressed.
+    /// Zero if the content is not compressed.
+    pub fn uncompressed_length(&self) -> u64 {
+        self.uncompressed_length
+    }
+
+    /// Get the uncompressed length mutably.
+    pub fn uncompressed_length_mut(&mut self) -> &mut u64 {
+        &mut self.uncompressed_length
+    }
+
+    /// Get the checksum type of the content.
+    pub fn checksum_type(&self) -> u32 {
+        self.checksum_type
+    }
+
+    /// Get the checksum type mutably.
+    pub fn checksum_type_mut(&mut self) -> &mut u32 {
+        &mut self.checksum_type
+    }
+
+    /// Get the compression codec of the content.
+    pub fn codec(&self) -> u32 {
+        self.codec
+    }
+
+    /// Get the compression codec mutably.
+    pub fn codec_mut(&mut self) -> &mut u32 {
+        &mut self.codec
+    }
+
+    /// Get the alignment of the content offset.
+    pub fn alignment(&self) -> u32 {
+        self.alignment
+    }
+
+    /// Get the alignment mutably.
+    pub fn alignment_mut(&mut self) -> &mut u32 {
+        &mut self.alignment
+    }
+
+    /// Get the serde encoding of the content.
+    pub fn encoding(&self) -> u32 {
+        self.encoding
+    }
+
+    /// Get the encoding mutably.
+    pub fn encoding_mut(&mut self) -> &mut u32 {
+        &mut self.encoding
+    }
+
     /// Read a table from the given stream.
     pub fn read<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
-        Ok(Self {
-            identifier: reader.read_u128::<E>()?.try_into()?,
+        let chunk = Self {
+            identifier: reader.read_u128::<E>()?.into(),
             length: reader.read_u64::<E>()?,
             offset: reader.read_u64::<E>()?,
-        })
+            checksum: reader.read_u64::<E>()?,
+            uncompressed_length: reader.read_u64::<E>()?,
+            checksum_type: reader.read_u32::<E>()?,
+            codec: reader.read_u32::<E>()?,
+            alignment: reader.read_u32::<E>()?,
+            encoding: reader.read_u32::<E>()?,
+        };
+        Ok(chunk)
     }
 
     /// Write a table to the given stream.
@@ -79,7 +196,42 @@ impl Chunk {
         writer.write_u128::<E>(*self.identifier)?;
         writer.write_u64::<E>(self.length)?;
         writer.write_u64::<E>(self.offset)?;
+        writer.write_u64::<E>(self.checksum)?;
+        writer.write_u64::<E>(self.uncompressed_length)?;
+        writer.write_u32::<E>(self.checksum_type)?;
+        writer.write_u32::<E>(self.codec)?;
+        writer.write_u32::<E>(self.alignment)?;
+        writer.write_u32::<E>(self.encoding)?;
 
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ecc;
+
+    #[test]
+    fn test_layout() {
+        assert_eq!(std::mem::size_of::<Chunk>(), 64);
+    }
+
+    #[test]
+    fn test_serialization() {
+        let mut chunk = Chunk::new((Ecc::new("test1"), Ecc::INVALID), 1, 2);
+        *chunk.checksum_mut() = 3;
+        *chunk.checksum_type_mut() = 4;
+        *chunk.codec_mut() = 5;
+        *chunk.uncompressed_length_mut() = 6;
+        *chunk.alignment_mut() = 4096;
+        *chunk.encoding_mut() = 7;
+
+        let mut buffer = vec![];
+        assert!(chunk.write::<crate::BE>(&mut buffer).is_ok());
+        assert_eq!(buffer.len(), Chunk::SIZE);
+
+        let result = Chunk::read::<crate::BE>(&mut buffer.as_slice()).unwrap();
+        assert_eq!(chunk, result);
+    }
+}
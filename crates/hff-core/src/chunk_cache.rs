@@ -1,4 +1,4 @@
-use crate::{ContentInfo, Error, Result};
+use crate::{ContentInfo, Error, Result, Section};
 
 /// Act as a ReadSeek IO object for purposes of having
 /// an entire HFF in memory at one time.
@@ -17,19 +17,22 @@ impl ChunkCache {
         }
     }
 
-    /// Get a slice representing the given content.
+    /// Get a slice representing the given content.  Empty content is an
+    /// empty slice wherever it claims to be.
     pub fn read(&self, content: &dyn ContentInfo) -> Result<&'_ [u8]> {
-        if content.len() > 0 {
-            assert!(
-                content.offset() >= self.offset,
-                "{} {}",
-                content.offset(),
-                self.offset
-            );
-            let offset = content.offset() - self.offset;
-            Ok(&self.buffer[offset as usize..(offset + content.len()) as usize])
-        } else {
-            Err(Error::Invalid("No data for this content.".into()))
+        if content.is_empty() {
+            return Ok(&[]);
         }
+        content
+            .offset()
+            .checked_sub(self.offset)
+            .and_then(|start| Some(start..start.checked_add(content.len())?))
+            .and_then(|range| {
+                self.buffer
+                    .get(usize::try_from(range.start).ok()?..usize::try_from(range.end).ok()?)
+            })
+            .ok_or(Error::Truncated {
+                section: Section::Content,
+            })
     }
 }
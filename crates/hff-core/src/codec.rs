@@ -0,0 +1,221 @@
+use crate::{Error, Result};
+use std::{
+    fmt::Display,
+    io::{Read, Write},
+    str::FromStr,
+};
+
+/// The compression codec applied to metadata or chunk content.
+/// Every codec can be named and stored, but compressing or
+/// decompressing requires the matching cargo feature:
+/// `compression` for xz, `zstd` and `lz4`.
+#[repr(u32)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Codec {
+    /// The content is stored as is.
+    #[default]
+    None = 0,
+    /// Lzma compression in the xz container.
+    Xz = 1,
+    /// Zstandard compression.
+    Zstd = 2,
+    /// Lz4 compression in the lz4 frame format.
+    Lz4 = 3,
+}
+
+impl Codec {
+    /// Check if support for the codec is compiled in.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::None => true,
+            Self::Xz => cfg!(feature = "compression"),
+            Self::Zstd => cfg!(feature = "zstd"),
+            Self::Lz4 => cfg!(feature = "lz4"),
+        }
+    }
+
+    /// Compress the data with the given level.  Levels are codec
+    /// specific: 0-9 for xz, 1-22 for zstd (0 is the zstd default)
+    /// and lz4 has no levels so it is ignored.
+    pub fn compress(&self, level: u32, data: &[u8]) -> Result<Vec<u8>> {
+        let mut result = vec![];
+        let mut encoder = self.encoder(level, &mut result)?;
+        encoder.write_all(data)?;
+        encoder.finish()?;
+        Ok(result)
+    }
+
+    /// Wrap the given writer in a streaming encoder so content can be
+    /// compressed straight into the output.  The encoder must be
+    /// finished to complete the compressed stream.
+    #[allow(unused_variables)]
+    pub fn encoder<'a>(
+        &self,
+        level: u32,
+        writer: impl Write + 'a,
+    ) -> Result<Box<dyn Encoder + 'a>> {
+        match self {
+            Self::None => Ok(Box::new(Stored(writer))),
+            #[cfg(feature = "compression")]
+            Self::Xz => Ok(Box::new(xz2::write::XzEncoder::new(writer, level.min(9)))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Box::new(zstd::stream::write::Encoder::new(
+                writer,
+                level.min(22) as i32,
+            )?)),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => Ok(Box::new(lz4_flex::frame::FrameEncoder::new(writer))),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unavailable()),
+        }
+    }
+
+    /// Decompress the data.
+    pub fn decompress(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut result = vec![];
+        self.decoder(data)?.read_to_end(&mut result)?;
+        Ok(result)
+    }
+
+    /// Wrap the given source in a streaming decoder so content can be
+    /// decompressed without holding all of it in memory.
+    pub fn decoder<'a>(&self, source: impl Read + 'a) -> Result<Box<dyn Read + 'a>> {
+        match self {
+            Self::None => Ok(Box::new(source)),
+            #[cfg(feature = "compression")]
+            Self::Xz => Ok(Box::new(xz2::read::XzDecoder::new(source))),
+            #[cfg(feature = "zstd")]
+            Self::Zstd => Ok(Box::new(zstd::stream::read::Decoder::new(source)?)),
+            #[cfg(feature = "lz4")]
+            Self::Lz4 => Ok(Box::new(lz4_flex::frame::FrameDecoder::new(source))),
+            #[allow(unreachable_patterns)]
+            _ => Err(self.unavailable()),
+        }
+    }
+
+    /// Error for codecs which were not compiled in.
+    #[allow(dead_code)]
+    fn unavailable(&self) -> Error {
+        Error::Invalid(format!("Codec {} is not enabled.", self))
+    }
+}
+
+/// A streaming compressor writing through to another writer.
+pub trait Encoder: Write {
+    /// Complete the compressed stream.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// Passes content through unchanged.
+struct Stored<W: Write>(W);
+
+impl<W: Write> Write for Stored<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl<W: Write> Encoder for Stored<W> {
+    fn finish(mut self: Box<Self>) -> Result<()> {
+        Ok(self.0.flush()?)
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<W: Write> Encoder for xz2::write::XzEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        (*self).finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<W: Write> Encoder for zstd::stream::write::Encoder<'static, W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        (*self).finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "lz4")]
+impl<W: Write> Encoder for lz4_flex::frame::FrameEncoder<W> {
+    fn finish(self: Box<Self>) -> Result<()> {
+        (*self)
+            .finish()
+            .map_err(|e| Error::Invalid(format!("Lz4 compression failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+impl TryFrom<u32> for Codec {
+    type Error = Error;
+
+    fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Xz),
+            2 => Ok(Self::Zstd),
+            3 => Ok(Self::Lz4),
+            _ => Err(Error::UnknownCodec(value)),
+        }
+    }
+}
+
+impl From<Codec> for u32 {
+    fn from(value: Codec) -> Self {
+        value as u32
+    }
+}
+
+impl Display for Codec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Xz => write!(f, "xz"),
+            Self::Zstd => write!(f, "zstd"),
+            Self::Lz4 => write!(f, "lz4"),
+        }
+    }
+}
+
+impl FromStr for Codec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "xz" | "lzma" => Ok(Self::Xz),
+            "zstd" => Ok(Self::Zstd),
+            "lz4" => Ok(Self::Lz4),
+            _ => Err(Error::Invalid(format!("Unknown codec: {}", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let data = "Some data to compress, some data to compress.".repeat(10);
+        for codec in [Codec::None, Codec::Xz, Codec::Zstd, Codec::Lz4] {
+            assert_eq!(codec.to_string().parse::<Codec>().unwrap(), codec);
+            if codec.is_available() {
+                let compressed = codec.compress(9, data.as_bytes()).unwrap();
+                assert_eq!(codec.decompress(&compressed).unwrap(), data.as_bytes());
+
+                let mut streamed = vec![];
+                let mut decoder = codec.decoder(compressed.as_slice()).unwrap();
+                decoder.read_to_end(&mut streamed).unwrap();
+                assert_eq!(streamed, data.as_bytes());
+            } else {
+                assert!(codec.compress(9, data.as_bytes()).is_err());
+            }
+        }
+    }
+}
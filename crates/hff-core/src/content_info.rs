@@ -1,9 +1,27 @@
+use crate::{Codec, Encoding, Result};
+
 /// Information about the metadata or chunk data contained within the source.
 pub trait ContentInfo {
     /// Length of the data.
     fn len(&self) -> u64;
+    /// Check if there is no data.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
     /// Offset into the overall source.
     fn offset(&self) -> u64;
+    /// The compression codec applied to the data.
+    fn codec(&self) -> Result<Codec> {
+        Ok(Codec::None)
+    }
+    /// Length of the data once decompressed.
+    fn uncompressed_len(&self) -> u64 {
+        self.len()
+    }
+    /// The serde encoding of the decompressed data.
+    fn encoding(&self) -> Result<Encoding> {
+        Ok(Encoding::None)
+    }
 }
 
 // Helper to use content info from provided data.
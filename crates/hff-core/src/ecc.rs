@@ -16,7 +16,7 @@ pub struct Ecc(
 
 impl Debug for Ecc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Ecc(\"{}\")", self.to_string())
+        write!(f, "Ecc(\"{}\")", self)
     }
 }
 
@@ -75,11 +75,11 @@ impl Ecc {
 
     /// Compare the Ecc's in two ways, native and opposing endians.
     /// If equivalent, returns Some with the endianess otherwise None.
-    /// NOTE: Will panic if rhs is a symetric id where endian can not
+    /// NOTE: Returns None if rhs is a symetric id where endian can not
     /// be detected.
     pub fn endian(&self, rhs: Self) -> Option<Endian> {
         if rhs.swap_bytes() == rhs {
-            panic!("Does not work for symetric ID's.")
+            return None;
         }
         if self.0 == rhs.0 {
             Some(crate::NATIVE_ENDIAN)
@@ -164,17 +164,16 @@ impl From<[u8; 8]> for Ecc {
     }
 }
 
-impl ToString for Ecc {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for Ecc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut name = String::with_capacity(8);
-        let code = self.as_slice();
         if self.is_valid() {
-            for i in 0..8 {
-                if code[i].is_ascii() {
-                    if code[i] == 0 {
+            for &code in self.as_slice() {
+                if code.is_ascii() {
+                    if code == 0 {
                         break;
                     } else {
-                        name.push(code[i] as char);
+                        name.push(code as char);
                     }
                 } else {
                     name = format!("{}", self.0);
@@ -184,7 +183,7 @@ impl ToString for Ecc {
         } else {
             name = "INVALID".into();
         }
-        name
+        write!(f, "{}", name)
     }
 }
 
@@ -202,14 +201,14 @@ mod tests {
     fn test_construction() {
         for i in 1..=8 {
             // Create and check the returned string.
-            let id = (0..i).into_iter().map(|_| 'x').collect::<String>();
+            let id = (0..i).map(|_| 'x').collect::<String>();
             let code = Ecc::new(&id);
             assert_eq!(code.to_string(), id);
 
             // Make sure all the remaining bytes are 0's.
             let bytes = code.as_slice();
-            for j in i..8 {
-                assert_eq!(bytes[j], 0);
+            for byte in bytes.iter().skip(i) {
+                assert_eq!(*byte, 0);
             }
         }
     }
@@ -246,5 +245,7 @@ mod tests {
             Ecc::from(Ecc::HFF_MAGIC.0.swap_bytes()).endian(Ecc::HFF_MAGIC),
             Some(crate::OPPOSING_ENDIAN)
         );
+        // Symetric codes can't be detected.
+        assert_eq!(Ecc::HFF_MAGIC.endian(Ecc::new("ssssssss")), None);
     }
 }
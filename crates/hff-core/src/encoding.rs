@@ -0,0 +1,146 @@
+use crate::Error;
+#[cfg(feature = "serde")]
+use crate::Result;
+use std::{fmt::Display, str::FromStr};
+
+/// The serde encoding of metadata or chunk content.  Every encoding
+/// can be named and stored, but encoding or decoding values requires
+/// the `serde` cargo feature.
+#[repr(u32)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// The content is raw bytes.
+    #[default]
+    None = 0,
+    /// Bincode with its default configuration.
+    Bincode = 1,
+    /// Concise binary object representation.
+    Cbor = 2,
+    /// Utf8 Json text.
+    Json = 3,
+    /// MessagePack with named struct fields.
+    MessagePack = 4,
+}
+
+impl Encoding {
+    /// Check if support for the encoding is compiled in.
+    pub fn is_available(&self) -> bool {
+        *self == Self::None || cfg!(feature = "serde")
+    }
+
+    /// Serialize the value with the encoding.
+    #[cfg(feature = "serde")]
+    pub fn encode<T: serde::Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::None => Err(Error::Invalid(
+                "Values can't be encoded as raw bytes.".into(),
+            )),
+            Self::Bincode => bincode::serialize(value).map_err(|e| self.failed(e)),
+            Self::Cbor => {
+                let mut result = vec![];
+                ciborium::into_writer(value, &mut result).map_err(|e| self.failed(e))?;
+                Ok(result)
+            }
+            Self::Json => serde_json::to_vec(value).map_err(|e| self.failed(e)),
+            Self::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| self.failed(e)),
+        }
+    }
+
+    /// Deserialize a value from data in the encoding.
+    #[cfg(feature = "serde")]
+    pub fn decode<T: serde::de::DeserializeOwned>(&self, data: &[u8]) -> Result<T> {
+        match self {
+            Self::None => Err(Error::Invalid("The content is not serde encoded.".into())),
+            Self::Bincode => bincode::deserialize(data).map_err(|e| self.failed(e)),
+            Self::Cbor => ciborium::from_reader(data).map_err(|e| self.failed(e)),
+            Self::Json => serde_json::from_slice(data).map_err(|e| self.failed(e)),
+            Self::MessagePack => rmp_serde::from_slice(data).map_err(|e| self.failed(e)),
+        }
+    }
+
+    /// Error for failed serialization.
+    #[cfg(feature = "serde")]
+    fn failed(&self, error: impl Display) -> Error {
+        Error::Invalid(format!("The {} encoding failed: {}", self, error))
+    }
+}
+
+impl TryFrom<u32> for Encoding {
+    type Error = Error;
+
+    fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Bincode),
+            2 => Ok(Self::Cbor),
+            3 => Ok(Self::Json),
+            4 => Ok(Self::MessagePack),
+            _ => Err(Error::UnknownEncoding(value)),
+        }
+    }
+}
+
+impl From<Encoding> for u32 {
+    fn from(value: Encoding) -> Self {
+        value as u32
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Bincode => write!(f, "bincode"),
+            Self::Cbor => write!(f, "cbor"),
+            Self::Json => write!(f, "json"),
+            Self::MessagePack => write!(f, "msgpack"),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "bincode" => Ok(Self::Bincode),
+            "cbor" => Ok(Self::Cbor),
+            "json" => Ok(Self::Json),
+            "msgpack" | "messagepack" => Ok(Self::MessagePack),
+            _ => Err(Error::Invalid(format!("Unknown encoding: {}", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for encoding in [
+            Encoding::None,
+            Encoding::Bincode,
+            Encoding::Cbor,
+            Encoding::Json,
+            Encoding::MessagePack,
+        ] {
+            assert_eq!(encoding.to_string().parse::<Encoding>().unwrap(), encoding);
+            assert_eq!(Encoding::try_from(u32::from(encoding)).unwrap(), encoding);
+
+            #[cfg(feature = "serde")]
+            if encoding != Encoding::None {
+                let value = (String::from("value"), vec![1_u32, 2, 3], Some(4.5_f64));
+                let data = encoding.encode(&value).unwrap();
+                assert_eq!(
+                    encoding
+                        .decode::<(String, Vec<u32>, Option<f64>)>(&data)
+                        .unwrap(),
+                    value
+                );
+            }
+        }
+        assert!(Encoding::try_from(5).is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::{Ecc, Version};
+use std::fmt::Display;
 use thiserror::Error;
 
 /// Common error type.
@@ -25,6 +27,70 @@ pub enum Error {
     /// Metadata is only allowed once on each table within the container.
     #[error("{0}")]
     DuplicateMetadata(String),
+    /// The structure of an hff stream is inconsistent.
+    #[error("{0}")]
+    InvalidStructure(String),
+    /// The stream does not start with the hff magic.
+    #[error("Not an hff stream.")]
+    BadMagic,
+    /// The header uses the footer layout but the stream does not end
+    /// with a footer.
+    #[error("Missing the hff footer.")]
+    MissingFooter,
+    /// The stream was written with a format version which can't be read.
+    #[error("Unsupported version {found}, expected {supported}.")]
+    UnsupportedVersion {
+        /// The version of the stream.
+        found: Version,
+        /// The version supported by this crate.
+        supported: Version,
+    },
+    /// The stream ends part way through a section.  Metadata or chunk
+    /// content lying past the end of the stream is reported within the
+    /// content by every reader.
+    #[error("The stream ends within the {section}.")]
+    Truncated {
+        /// The section being read.
+        section: Section,
+    },
+    /// A table refers to chunks past the end of the chunk array.
+    #[error("Chunk {index} is out of bounds.")]
+    OutOfBounds {
+        /// The first index which does not exist.
+        index: u64,
+    },
+    /// The stream uses a layout the reader does not support, such as the
+    /// footer layout with the async readers.
+    #[error("The footer layout is not supported by this reader.")]
+    UnsupportedLayout,
+    /// Decompressed content does not have the length recorded for it.
+    #[error("Decompressed {found} bytes, expected {expected}.")]
+    LengthMismatch {
+        /// The length recorded in the structure.
+        expected: u64,
+        /// The length found, limited to one past the expected length.
+        found: u64,
+    },
+    /// The identifier type in the header is unknown.
+    #[error("Unknown identifier type: {0}")]
+    UnknownIdType(u32),
+    /// A table or chunk uses an unknown compression codec.
+    #[error("Unknown codec: {0}")]
+    UnknownCodec(u32),
+    /// A table or chunk uses an unknown serde encoding.
+    #[error("Unknown encoding: {0}")]
+    UnknownEncoding(u32),
+    /// A table or chunk uses an unknown checksum type.
+    #[error("Unknown checksum type: {0}")]
+    UnknownChecksum(u32),
+    /// Utility data does not start with the identifier of its type.
+    #[error("Expected {expected} data, found {found}.")]
+    UnexpectedId {
+        /// The identifier of the type being read.
+        expected: Ecc,
+        /// The identifier found.
+        found: Ecc,
+    },
     /// File manipulation error.
     #[error("{0}")]
     StripPrefixError(#[from] std::path::StripPrefixError),
@@ -33,5 +99,45 @@ pub enum Error {
     IoError(#[from] std::io::Error),
 }
 
+impl Error {
+    /// Report an unexpected end of stream as truncation of the given
+    /// section, other errors are returned unchanged.
+    pub fn within(self, section: Section) -> Self {
+        match self {
+            Self::IoError(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                Self::Truncated { section }
+            }
+            e => e,
+        }
+    }
+}
+
+/// The parts of an hff stream, used to report where reading failed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The header at the start of the stream.
+    Header,
+    /// The table array.
+    Tables,
+    /// The chunk array.
+    Chunks,
+    /// Metadata or chunk content.
+    Content,
+    /// The footer at the end of the stream.
+    Footer,
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Header => write!(f, "header"),
+            Self::Tables => write!(f, "table array"),
+            Self::Chunks => write!(f, "chunk array"),
+            Self::Content => write!(f, "content"),
+            Self::Footer => write!(f, "footer"),
+        }
+    }
+}
+
 /// The standard result type used in the crate.
 pub type Result<T> = std::result::Result<T, crate::Error>;
@@ -0,0 +1,121 @@
+//! The trailer of the footer layout.
+use crate::{Chunk, Ecc, Error, Header, Result, Table};
+use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+/// Files written in a single pass can't go back and fill in the table
+/// and chunk arrays after the data, so they use the footer layout: the
+/// header counts are set to Header::FOOTER, the data follows the header
+/// and the arrays follow the data.  This fixed size trailer ends the
+/// file and points back at the arrays.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Footer {
+    /// Magic identifier.  Footer::MAGIC
+    magic: Ecc,
+    /// Offset from the start of the file to the table array.
+    directory_offset: u64,
+    /// Total count of tables.
+    table_count: u32,
+    /// Total count of chunks.
+    chunk_count: u32,
+}
+
+impl Footer {
+    /// Size of the footer.
+    pub const SIZE: usize = std::mem::size_of::<Self>();
+
+    /// Magic identifier of the footer.
+    pub const MAGIC: Ecc = Ecc::new("HFF-FOOT");
+
+    /// Create a new instance.
+    pub fn new(directory_offset: u64, table_count: u32, chunk_count: u32) -> Self {
+        Self {
+            magic: Self::MAGIC,
+            directory_offset,
+            table_count,
+            chunk_count,
+        }
+    }
+
+    /// Get the offset to the table array.
+    pub fn directory_offset(&self) -> u64 {
+        self.directory_offset
+    }
+
+    /// Get the table count.
+    pub fn table_count(&self) -> u32 {
+        self.table_count
+    }
+
+    /// Get the chunk count.
+    pub fn chunk_count(&self) -> u32 {
+        self.chunk_count
+    }
+
+    /// Size of the table and chunk arrays the footer points at.
+    pub fn arrays_size(&self) -> u64 {
+        self.table_count as u64 * Table::SIZE as u64 + self.chunk_count as u64 * Chunk::SIZE as u64
+    }
+
+    /// The header with the counts filled in from the footer.
+    pub fn header(&self, header: &Header) -> Header {
+        Header::with(
+            header.magic(),
+            header.version(),
+            header.id_type_value(),
+            header.content_type(),
+            self.table_count,
+            self.chunk_count,
+        )
+    }
+
+    /// Read a footer from the given stream.
+    pub fn read<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
+        let magic = Ecc::read::<E>(reader)?;
+        if magic != Self::MAGIC {
+            return Err(Error::MissingFooter);
+        }
+        Ok(Self {
+            magic,
+            directory_offset: reader.read_u64::<E>()?,
+            table_count: reader.read_u32::<E>()?,
+            chunk_count: reader.read_u32::<E>()?,
+        })
+    }
+
+    /// Write the footer to the given stream.
+    pub fn write<E: ByteOrder>(self, writer: &mut dyn Write) -> Result<()> {
+        self.magic.write::<E>(writer)?;
+        writer.write_u64::<E>(self.directory_offset)?;
+        writer.write_u32::<E>(self.table_count)?;
+        writer.write_u32::<E>(self.chunk_count)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BE, LE};
+
+    #[test]
+    fn test_struct_layout() {
+        assert_eq!(Footer::SIZE, 24);
+    }
+
+    #[test]
+    fn serialization() {
+        let footer = Footer::new(1024, 3, 7);
+
+        let mut buffer = vec![];
+        footer.write::<LE>(&mut buffer).unwrap();
+        assert_eq!(buffer.len(), Footer::SIZE);
+        assert_eq!(Footer::read::<LE>(&mut buffer.as_slice()).unwrap(), footer);
+
+        let mut buffer = vec![];
+        footer.write::<BE>(&mut buffer).unwrap();
+        assert_eq!(Footer::read::<BE>(&mut buffer.as_slice()).unwrap(), footer);
+        assert!(Footer::read::<LE>(&mut buffer.as_slice()).is_err());
+    }
+}
@@ -1,10 +1,10 @@
 //! The file header.
-use crate::{Ecc, Endian, Error, IdType, Result, Version, BE, LE, NATIVE_ENDIAN, NE};
+use crate::{Ecc, Endian, Error, IdType, Result, Section, Version, BE, LE, NATIVE_ENDIAN, NE};
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use std::io::Write;
 
 /// The current version of the format.
-pub const FORMAT_VERSION: Version = Version::new(0, 3);
+pub const FORMAT_VERSION: Version = Version::new(0, 7);
 
 /// The file header.
 #[repr(C)]
@@ -33,6 +33,10 @@ impl Header {
     /// Size of the header.
     pub const SIZE: usize = std::mem::size_of::<Self>();
 
+    /// Table and chunk count marking the footer layout, the real counts
+    /// are stored in the Footer at the end of the file.
+    pub const FOOTER: u32 = u32::MAX;
+
     /// Create a new instance.
     pub fn new(id_type: IdType, content_type: Ecc, table_count: u32, chunk_count: u32) -> Self {
         Self {
@@ -45,6 +49,11 @@ impl Header {
         }
     }
 
+    /// Create a header for the footer layout.
+    pub fn footer(id_type: IdType, content_type: Ecc) -> Self {
+        Self::new(id_type, content_type, Self::FOOTER, Self::FOOTER)
+    }
+
     /// Create a new instance with the given data.
     pub fn with(
         magic: Ecc,
@@ -94,8 +103,15 @@ impl Header {
     }
 
     /// Identifier type.
+    /// Headers read from a stream always have a known type, an unknown
+    /// value from a hand built header is reported as IdType::Id.
     pub fn id_type(&self) -> IdType {
-        self.id_type.into()
+        IdType::try_from(self.id_type).unwrap_or(IdType::Id)
+    }
+
+    /// Identifier type as stored, which may not be a known IdType.
+    pub fn id_type_value(&self) -> u32 {
+        self.id_type
     }
 
     /// What's the endian?
@@ -103,6 +119,11 @@ impl Header {
         self.magic == Ecc::HFF_MAGIC
     }
 
+    /// Check if the table and chunk arrays are found through a Footer.
+    pub fn has_footer(&self) -> bool {
+        self.table_count == Self::FOOTER && self.chunk_count == Self::FOOTER
+    }
+
     /// Get the table count.
     pub fn table_count(&self) -> u32 {
         self.table_count
@@ -146,6 +167,11 @@ impl TryFrom<&[u8]> for Header {
     type Error = crate::Error;
 
     fn try_from(mut value: &[u8]) -> std::prelude::v1::Result<Self, Self::Error> {
+        if value.len() < Self::SIZE {
+            return Err(Error::Truncated {
+                section: Section::Header,
+            });
+        }
         let reader: &mut dyn std::io::Read = &mut value;
 
         // Read the magic in native endian.
@@ -154,7 +180,7 @@ impl TryFrom<&[u8]> for Header {
         // Check the endianness and read the remaining data appropriately.
         // NOTE: The magic is stored as whatever form was found so we can
         // detect the original form at a later time.
-        match Ecc::HFF_MAGIC.endian(magic.clone()) {
+        let header = match Ecc::HFF_MAGIC.endian(magic) {
             Some(endian) => match endian {
                 Endian::Little => Ok(Header::with(
                     magic,
@@ -173,8 +199,18 @@ impl TryFrom<&[u8]> for Header {
                     reader.read_u32::<BE>()?,
                 )),
             },
-            None => Err(Error::Invalid("Not an HFF file.".into())),
+            None => Err(Error::BadMagic),
+        }?;
+
+        // Reject versions and identifier types we don't know about.
+        if header.version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion {
+                found: header.version,
+                supported: FORMAT_VERSION,
+            });
         }
+        IdType::try_from(header.id_type)?;
+        Ok(header)
     }
 }
 
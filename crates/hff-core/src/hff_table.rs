@@ -0,0 +1,76 @@
+use crate::{
+    read::{ContentReader, Hff, TableView},
+    write::{DataSource, TableBuilder},
+    ContentInfo, Error, Identifier, Result,
+};
+use std::fmt::Debug;
+
+/// Types which map onto an hff table.  Usually implemented with
+/// `#[derive(HffTable)]` from the `hff-derive` crate.
+pub trait HffTable: Sized {
+    /// The identifier of the table.
+    fn identifier() -> Identifier;
+
+    /// Build the table holding the value.
+    fn to_table(&self) -> Result<TableBuilder<'_>>;
+
+    /// Reconstruct the value from the table.
+    fn from_table<T: Debug + ContentReader>(table: &TableView<'_, T>) -> Result<Self>;
+
+    /// Reconstruct the value from the first child of the parent with
+    /// the identifier of the type.
+    fn from_child<T: Debug + ContentReader>(parent: &TableView<'_, T>) -> Result<Self> {
+        let child = parent.find_child(Self::identifier()).ok_or_else(|| {
+            Error::NotFound(format!(
+                "Missing child table {:?} in {:?}.",
+                Self::identifier(),
+                parent.identifier()
+            ))
+        })?;
+        Self::from_table(&child)
+    }
+
+    /// Reconstruct a value from each child of the parent with the
+    /// identifier of the type, in order.
+    fn from_children<T: Debug + ContentReader>(parent: &TableView<'_, T>) -> Result<Vec<Self>> {
+        parent
+            .iter()
+            .filter(|child| child.identifier() == Self::identifier())
+            .map(|child| Self::from_table(&child))
+            .collect()
+    }
+}
+
+/// Types which can be stored as table metadata or chunk content.
+pub trait HffContent: Sized {
+    /// Get the data source to write.
+    fn to_source(&self) -> Result<DataSource<'_>>;
+
+    /// Reconstruct the value from the decompressed content.
+    fn from_content(data: Vec<u8>) -> Result<Self>;
+
+    /// Read and reconstruct the value from a chunk or table metadata.
+    fn read<T: Debug + ContentReader>(hff: &Hff<T>, content: &dyn ContentInfo) -> Result<Self> {
+        Self::from_content(hff.get_decompressed(content)?)
+    }
+}
+
+impl HffContent for Vec<u8> {
+    fn to_source(&self) -> Result<DataSource<'_>> {
+        Ok(DataSource::Ref(self))
+    }
+
+    fn from_content(data: Vec<u8>) -> Result<Self> {
+        Ok(data)
+    }
+}
+
+impl HffContent for String {
+    fn to_source(&self) -> Result<DataSource<'_>> {
+        Ok(DataSource::Ref(self.as_bytes()))
+    }
+
+    fn from_content(data: Vec<u8>) -> Result<Self> {
+        String::from_utf8(data).map_err(|e| Error::Utf8Error(e.utf8_error()))
+    }
+}
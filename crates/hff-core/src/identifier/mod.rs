@@ -37,16 +37,18 @@ impl Deref for IdType {
     }
 }
 
-impl From<u32> for IdType {
-    fn from(value: u32) -> Self {
+impl TryFrom<u32> for IdType {
+    type Error = Error;
+
+    fn try_from(value: u32) -> std::result::Result<Self, Self::Error> {
         match value {
-            0 => Self::Id,
-            1 => Self::Ecc2,
-            2 => Self::Uuid,
-            3 => Self::Au8,
-            4 => Self::EccU64,
-            5 => Self::U64s,
-            _ => panic!("Invalid identifier type in header."),
+            0 => Ok(Self::Id),
+            1 => Ok(Self::Ecc2),
+            2 => Ok(Self::Uuid),
+            3 => Ok(Self::Au8),
+            4 => Ok(Self::EccU64),
+            5 => Ok(Self::U64s),
+            _ => Err(Error::UnknownIdType(value)),
         }
     }
 }
@@ -70,11 +72,11 @@ impl Identifier {
             IdType::Id => format!("{:X}", self.0),
             IdType::Ecc2 => {
                 let (p, s): (Ecc, Ecc) = (*self).into();
-                format!("{}:{}", p.to_string(), s.to_string())
+                format!("{}:{}", p, s)
             }
             IdType::Uuid => {
                 let id: Uuid = (*self).into();
-                format!("{}", id.to_string())
+                id.to_string()
             }
             IdType::Au8 => {
                 let chars: [u8; 16] = (*self).into();
@@ -82,7 +84,7 @@ impl Identifier {
             }
             IdType::EccU64 => {
                 let (ecc, value): (Ecc, u64) = (*self).into();
-                format!("{}:{:X}", ecc.to_string(), value)
+                format!("{}:{:X}", ecc, value)
             }
             IdType::U64s => {
                 let (l, r): (u64, u64) = (*self).into();
@@ -143,39 +145,39 @@ impl DerefMut for Identifier {
     }
 }
 
-impl Into<u128> for Identifier {
-    fn into(self) -> u128 {
-        self.0
+impl From<Identifier> for u128 {
+    fn from(value: Identifier) -> Self {
+        value.0
     }
 }
 
-impl Into<(Ecc, Ecc)> for Identifier {
-    fn into(self) -> (Ecc, Ecc) {
-        self.as_ecc2()
+impl From<Identifier> for (Ecc, Ecc) {
+    fn from(value: Identifier) -> Self {
+        value.as_ecc2()
     }
 }
 
-impl Into<Uuid> for Identifier {
-    fn into(self) -> Uuid {
-        self.as_uuid()
+impl From<Identifier> for Uuid {
+    fn from(value: Identifier) -> Self {
+        value.as_uuid()
     }
 }
 
-impl Into<[u8; 16]> for Identifier {
-    fn into(self) -> [u8; 16] {
-        self.as_au8()
+impl From<Identifier> for [u8; 16] {
+    fn from(value: Identifier) -> Self {
+        value.as_au8()
     }
 }
 
-impl Into<(Ecc, u64)> for Identifier {
-    fn into(self) -> (Ecc, u64) {
-        self.as_eccu64()
+impl From<Identifier> for (Ecc, u64) {
+    fn from(value: Identifier) -> Self {
+        value.as_eccu64()
     }
 }
 
-impl Into<(u64, u64)> for Identifier {
-    fn into(self) -> (u64, u64) {
-        self.as_u64s()
+impl From<Identifier> for (u64, u64) {
+    fn from(value: Identifier) -> Self {
+        value.as_u64s()
     }
 }
 
@@ -238,9 +240,9 @@ impl TryFrom<(&str, &str)> for Identifier {
     type Error = Error;
 
     fn try_from(value: (&str, &str)) -> Result<Self> {
-        let primary: Ecc = value.0.try_into()?;
-        let secondary: Ecc = value.1.try_into()?;
-        Ok((primary, secondary).try_into()?)
+        let primary: Ecc = value.0.into();
+        let secondary: Ecc = value.1.into();
+        Ok((primary, secondary).into())
     }
 }
 
@@ -248,8 +250,8 @@ impl TryFrom<(&str, u64)> for Identifier {
     type Error = Error;
 
     fn try_from(value: (&str, u64)) -> Result<Self> {
-        let ecc: Ecc = value.0.try_into()?;
-        Ok((ecc, value.1).try_into()?)
+        let ecc: Ecc = value.0.into();
+        Ok((ecc, value.1).into())
     }
 }
 
@@ -259,9 +261,9 @@ mod tests {
 
     #[test]
     fn test_ecc2() {
-        let identifier: Identifier = (123, 456).try_into().unwrap();
-        let (_123, _456) = identifier.as_u64s();
-        assert_eq!(_123, 123);
-        assert_eq!(_456, 456);
+        let identifier: Identifier = (123, 456).into();
+        let (primary, secondary) = identifier.as_u64s();
+        assert_eq!(primary, 123);
+        assert_eq!(secondary, 456);
     }
 }
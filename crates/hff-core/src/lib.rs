@@ -9,7 +9,7 @@ pub use endian::*;
 
 // The crate error and result types.
 mod error;
-pub use error::{Error, Result};
+pub use error::{Error, Result, Section};
 
 // We can use a number of different identification
 // schemes within tables and chunks.  Different
@@ -29,6 +29,10 @@ pub use version::Version;
 mod header;
 pub use header::Header;
 
+// The trailer of single pass files.
+mod footer;
+pub use footer::Footer;
+
 // A table in the structure.
 mod table;
 pub use table::Table;
@@ -37,6 +41,18 @@ pub use table::Table;
 mod chunk;
 pub use chunk::Chunk;
 
+// Content checksums.
+mod checksum;
+pub use checksum::{Checksum, ChecksumHasher};
+
+// Compression codecs.
+mod codec;
+pub use codec::{Codec, Encoder};
+
+// Serde encodings.
+mod encoding;
+pub use encoding::Encoding;
+
 // Helper for full file reading.
 mod chunk_cache;
 pub use chunk_cache::ChunkCache;
@@ -47,6 +63,10 @@ pub mod read;
 // Write support.
 pub mod write;
 
+// Mapping of types onto tables.
+mod hff_table;
+pub use hff_table::{HffContent, HffTable};
+
 // Information about metadata or chunk data.
 mod content_info;
 pub use content_info::ContentInfo;
@@ -10,11 +10,13 @@ pub struct ChunkIter<'a, T: Debug> {
 
 impl<'a, T: Debug> ChunkIter<'a, T> {
     /// Create a new chunk iterator.
+    /// The range is limited to the chunks which actually exist.
     pub fn new(hff: &'a Hff<T>, index: usize, count: usize) -> Self {
+        let available = hff.chunks_array().len().saturating_sub(index);
         Self {
             hff,
             current: index as isize - 1,
-            count,
+            count: count.min(available),
         }
     }
 
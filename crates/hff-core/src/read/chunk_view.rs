@@ -1,14 +1,22 @@
-use super::Hff;
-use crate::{ContentInfo, Identifier};
+use super::{verify::verify_content, ContentReader, Hff};
+use crate::{Codec, ContentInfo, Encoding, Identifier, Result};
 use std::fmt::Debug;
 
 /// A view to a chunk.
-#[derive(Copy, Clone)]
 pub struct ChunkView<'a, T: Debug> {
     hff: &'a Hff<T>,
     index: usize,
 }
 
+// Views only hold a reference so they copy regardless of the accessor.
+impl<'a, T: Debug> Clone for ChunkView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: Debug> Copy for ChunkView<'a, T> {}
+
 impl<'a, T: Debug> Debug for ChunkView<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.hff.chunks_array()[self.index])
@@ -22,7 +30,7 @@ impl<'a, T: Debug> ChunkView<'a, T> {
     }
 
     /// Get the hff this was built from.
-    pub fn hff(&self) -> &Hff<T> {
+    pub fn hff(&self) -> &'a Hff<T> {
         self.hff
     }
 
@@ -40,6 +48,26 @@ impl<'a, T: Debug> ChunkView<'a, T> {
     pub fn size(&self) -> usize {
         self.hff.chunks_array()[self.index].length() as usize
     }
+
+    /// Get the alignment of the chunk offset.
+    pub fn alignment(&self) -> u32 {
+        self.hff.chunks_array()[self.index].alignment()
+    }
+}
+
+impl<'a, T: Debug + ContentReader> ChunkView<'a, T> {
+    /// Verify the chunk content against the stored checksum.
+    /// Chunks without a checksum always pass.
+    pub fn verify(&self) -> bool {
+        let chunk = &self.hff.chunks_array()[self.index];
+        verify_content(&**self.hff, self, chunk.checksum_type(), chunk.checksum())
+    }
+
+    /// Deserialize the chunk with the serde encoding it was written with.
+    #[cfg(feature = "serde")]
+    pub fn decode<D: serde::de::DeserializeOwned>(&self) -> Result<D> {
+        self.hff.get_decoded(self)
+    }
 }
 
 impl<'a, T: Debug> ContentInfo for ChunkView<'a, T> {
@@ -50,4 +78,21 @@ impl<'a, T: Debug> ContentInfo for ChunkView<'a, T> {
     fn offset(&self) -> u64 {
         self.hff.chunks_array()[self.index].offset()
     }
+
+    fn codec(&self) -> Result<Codec> {
+        Codec::try_from(self.hff.chunks_array()[self.index].codec())
+    }
+
+    fn uncompressed_len(&self) -> u64 {
+        let chunk = &self.hff.chunks_array()[self.index];
+        if chunk.codec() == u32::from(Codec::None) {
+            chunk.length()
+        } else {
+            chunk.uncompressed_length()
+        }
+    }
+
+    fn encoding(&self) -> Result<Encoding> {
+        Encoding::try_from(self.hff.chunks_array()[self.index].encoding())
+    }
 }
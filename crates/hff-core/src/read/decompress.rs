@@ -3,7 +3,7 @@ use crate::Result;
 /// Decompress the provided data.
 #[cfg(feature = "compression")]
 pub fn decompress(source: &[u8]) -> Result<Vec<u8>> {
-    if source.len() > 0 {
+    if !source.is_empty() {
         let mut decoder = xz2::read::XzDecoder::new(source);
         let mut result = vec![];
         std::io::copy(&mut decoder, &mut result)?;
@@ -19,7 +19,7 @@ pub fn decompress(source: &[u8]) -> Result<Vec<u8>> {
 pub fn decompress_exact(source: &[u8], mut destination: &mut [u8]) -> Result<()> {
     use crate::Error;
 
-    if source.len() > 0 && destination.len() > source.len() {
+    if !source.is_empty() && destination.len() > source.len() {
         let mut decoder = xz2::read::XzDecoder::new(source);
         std::io::copy(&mut decoder, &mut destination)?;
         Ok(())
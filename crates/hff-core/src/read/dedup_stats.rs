@@ -0,0 +1,39 @@
+use crate::{Chunk, Table};
+use std::collections::HashSet;
+
+/// Describes how much content of an hff is shared between metadata and
+/// chunk entries, such as the output of a deduplicating write.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Metadata and chunk entries with content.
+    pub entries: usize,
+    /// Entries pointing at content already stored for another entry.
+    pub shared: usize,
+    /// Bytes the shared entries would have taken if stored separately.
+    pub bytes_saved: u64,
+}
+
+impl DedupStats {
+    /// Compute the statistics for the given structure.
+    pub fn new(tables: &[Table], chunks: &[Chunk]) -> Self {
+        let metadata = tables
+            .iter()
+            .map(|table| (table.metadata_offset(), table.metadata_length()));
+        let chunks = chunks.iter().map(|chunk| (chunk.offset(), chunk.length()));
+        Self::from_content(metadata.chain(chunks))
+    }
+
+    /// Compute the statistics from the offset and length of each entry.
+    pub fn from_content(content: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut stats = Self::default();
+        let mut seen = HashSet::new();
+        for (offset, length) in content.into_iter().filter(|(_, length)| *length > 0) {
+            stats.entries += 1;
+            if !seen.insert((offset, length)) {
+                stats.shared += 1;
+                stats.bytes_saved += length;
+            }
+        }
+        stats
+    }
+}
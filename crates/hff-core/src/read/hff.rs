@@ -1,7 +1,10 @@
-use super::{DepthFirstIter, TableIter};
-use crate::{Chunk, Ecc, Header, IdType, Table, Version};
+use super::{
+    verify::verify_content, ContentReader, Corrupt, DedupStats, DepthFirstIter, TableIter,
+};
+use crate::{Chunk, ContentInfo, Ecc, Error, Header, IdType, Result, Table, Version};
 use std::{
     fmt::Debug,
+    io::Read,
     mem::size_of,
     ops::{Deref, DerefMut},
 };
@@ -91,6 +94,95 @@ impl<T: Debug> Hff<T> {
     pub fn chunks_array(&self) -> &[Chunk] {
         &self.chunks
     }
+
+    /// Report how much content is shared between entries.
+    pub fn dedup_stats(&self) -> DedupStats {
+        DedupStats::new(&self.tables, &self.chunks)
+    }
+}
+
+impl<T: Debug + ContentReader> Hff<T> {
+    /// Get the content of the given chunk or table metadata, decompressed
+    /// with the codec recorded in the file.  Uncompressed content is
+    /// returned as is.  Decompression stops one byte past the recorded
+    /// length so corrupt content can't expand without limit.
+    pub fn get_decompressed(&self, content: &dyn ContentInfo) -> Result<Vec<u8>> {
+        let codec = content.codec()?;
+        let source = self.accessor.content(content)?;
+        let mut data = vec![];
+        codec
+            .decoder(source.as_ref())?
+            .take(content.uncompressed_len().saturating_add(1))
+            .read_to_end(&mut data)?;
+        if data.len() as u64 == content.uncompressed_len() {
+            Ok(data)
+        } else {
+            Err(Error::LengthMismatch {
+                expected: content.uncompressed_len(),
+                found: data.len() as u64,
+            })
+        }
+    }
+
+    /// Deserialize the given chunk or table metadata with the serde
+    /// encoding recorded in the file.  Content which was not written
+    /// through serde is an error.
+    #[cfg(feature = "serde")]
+    pub fn get_decoded<D: serde::de::DeserializeOwned>(
+        &self,
+        content: &dyn ContentInfo,
+    ) -> Result<D> {
+        let encoding = content.encoding()?;
+        encoding.decode(&self.get_decompressed(content)?)
+    }
+
+    /// Get a reader which decompresses the given chunk or table metadata
+    /// as it is read.  Only the part of the source holding the content is
+    /// read and at most the recorded uncompressed length is produced, so
+    /// large content can be processed in constant memory.
+    pub fn decompressed_reader(&self, content: &dyn ContentInfo) -> Result<impl Read + '_> {
+        let codec = content.codec()?;
+        let source = self.accessor.stream(content)?;
+        Ok(codec.decoder(source)?.take(content.uncompressed_len()))
+    }
+
+    /// Verify the metadata and chunks against their stored checksums.
+    /// Returns every item which failed, an empty result means all
+    /// content is intact.  Content without a checksum is not checked.
+    pub fn verify(&self) -> Vec<Corrupt> {
+        let mut corrupt = vec![];
+        for (index, table) in self.tables.iter().enumerate() {
+            let content = (table.metadata_length(), table.metadata_offset());
+            if table.metadata_length() > 0
+                && !verify_content(
+                    &self.accessor,
+                    &content,
+                    table.checksum_type(),
+                    table.metadata_checksum(),
+                )
+            {
+                corrupt.push(Corrupt::Metadata {
+                    table: index,
+                    identifier: table.identifier(),
+                });
+            }
+        }
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let content = (chunk.length(), chunk.offset());
+            if !verify_content(
+                &self.accessor,
+                &content,
+                chunk.checksum_type(),
+                chunk.checksum(),
+            ) {
+                corrupt.push(Corrupt::Chunk {
+                    chunk: index,
+                    identifier: chunk.identifier(),
+                });
+            }
+        }
+        corrupt
+    }
 }
 
 impl<T: Debug> Deref for Hff<T> {
@@ -106,3 +198,34 @@ impl<T: Debug> DerefMut for Hff<T> {
         &mut self.accessor
     }
 }
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use crate::{read::ChunkView, ChunkCache, Codec};
+
+    #[test]
+    fn bounded_decompression() {
+        // A megabyte of zeros compresses to a tiny chunk which claims
+        // to expand to only a few bytes.
+        let data = Codec::Xz.compress(6, &vec![0; 1 << 20]).unwrap();
+        let mut chunk = Chunk::new((Ecc::new("Bomb"), Ecc::INVALID), data.len() as u64, 0);
+        *chunk.codec_mut() = Codec::Xz.into();
+        *chunk.uncompressed_length_mut() = 16;
+        let hff = Hff::new(
+            ChunkCache::new(0, data),
+            Header::new(IdType::Ecc2, Ecc::new("Test"), 0, 1),
+            vec![],
+            vec![chunk],
+        );
+
+        let chunk = ChunkView::new(&hff, 0);
+        assert!(matches!(
+            hff.get_decompressed(&chunk),
+            Err(Error::LengthMismatch {
+                expected: 16,
+                found: 17
+            })
+        ));
+    }
+}
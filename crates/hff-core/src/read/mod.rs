@@ -27,6 +27,18 @@ pub use decompress::{decompress, decompress_exact};
 mod hff;
 pub use hff::Hff;
 
+mod path;
+pub use path::{Matches, PathIndex};
+
+mod dedup_stats;
+pub use dedup_stats::DedupStats;
+
+mod validate;
+pub use validate::{validate, validate_footer, validate_footer_header, validate_header};
+
+mod verify;
+pub use verify::{ContentReader, Corrupt};
+
 /// Iteration data attached to the Hff for varying access needs.
 pub trait IterData: std::fmt::Debug + Copy + Clone + Default {}
 
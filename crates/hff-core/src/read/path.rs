@@ -0,0 +1,414 @@
+use super::{ChunkView, Hff, TableView};
+use crate::{Error, Identifier, Result};
+use std::{collections::HashMap, fmt::Debug};
+
+/// The tables and chunks found by a path lookup, each in file order.
+#[derive(Debug)]
+pub struct Matches<'a, T: Debug> {
+    /// Tables matched by the full path.
+    pub tables: Vec<TableView<'a, T>>,
+    /// Chunks matched by the last part of the path within the tables
+    /// matched by the rest of it.
+    pub chunks: Vec<ChunkView<'a, T>>,
+}
+
+impl<'a, T: Debug> Matches<'a, T> {
+    /// Check if nothing matched.
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty() && self.chunks.is_empty()
+    }
+
+    /// Get the first matching table.
+    pub fn table(&self) -> Option<TableView<'a, T>> {
+        self.tables.first().copied()
+    }
+
+    /// Get the first matching chunk.
+    pub fn chunk(&self) -> Option<ChunkView<'a, T>> {
+        self.chunks.first().copied()
+    }
+
+    /// Create the matches from table and chunk indices.
+    fn from_indices(hff: &'a Hff<T>, tables: &[usize], chunks: &[usize]) -> Self {
+        Self {
+            tables: tables.iter().map(|i| TableView::new(hff, *i)).collect(),
+            chunks: chunks.iter().map(|i| ChunkView::new(hff, *i)).collect(),
+        }
+    }
+
+    /// Put the matches in file order and drop any found twice.
+    fn sort(&mut self) {
+        self.tables.sort_by_key(|table| table.index());
+        self.tables.dedup_by_key(|table| table.index());
+        self.chunks.sort_by_key(|chunk| chunk.index());
+        self.chunks.dedup_by_key(|chunk| chunk.index());
+    }
+}
+
+/// Precomputed lookup from every full identifier path in an hff to the
+/// tables and chunks found there, for repeated exact lookups.
+#[derive(Debug)]
+pub struct PathIndex<'a, T: Debug> {
+    hff: &'a Hff<T>,
+    paths: HashMap<Vec<Identifier>, (Vec<usize>, Vec<usize>)>,
+}
+
+impl<'a, T: Debug> PathIndex<'a, T> {
+    /// Index all the paths of the hff.
+    pub fn new(hff: &'a Hff<T>) -> Self {
+        let mut index = Self {
+            hff,
+            paths: HashMap::new(),
+        };
+        let mut path = vec![];
+        for table in hff.tables() {
+            index.insert(&mut path, table);
+        }
+        index
+    }
+
+    /// Get the number of distinct paths.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Check if the hff had no content to index.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Find the tables and chunks with exactly the given path, the same
+    /// as `Hff::find` without walking the structure.
+    pub fn find<I: Into<Identifier> + Copy>(&self, path: &[I]) -> Matches<'a, T> {
+        let path = path.iter().map(|id| (*id).into()).collect::<Vec<_>>();
+        match self.paths.get(&path) {
+            Some((tables, chunks)) => Matches::from_indices(self.hff, tables, chunks),
+            None => Matches::from_indices(self.hff, &[], &[]),
+        }
+    }
+
+    /// Add the table, its chunks and its children below the parent path.
+    fn insert(&mut self, path: &mut Vec<Identifier>, table: TableView<'a, T>) {
+        path.push(table.identifier());
+        self.paths
+            .entry(path.clone())
+            .or_default()
+            .0
+            .push(table.index());
+        for chunk in table.chunks() {
+            path.push(chunk.identifier());
+            self.paths
+                .entry(path.clone())
+                .or_default()
+                .1
+                .push(chunk.index());
+            path.pop();
+        }
+        for child in table.iter() {
+            self.insert(path, child);
+        }
+        path.pop();
+    }
+}
+
+/// A single part of a lookup path.
+#[derive(Debug)]
+enum Segment {
+    /// Matches one identifier exactly.
+    Exact(Identifier),
+    /// Matches Ecc2 identifiers whose primary and secondary match the
+    /// glob patterns.
+    Glob(String, String),
+    /// Matches any number of levels, including none.
+    AnyDepth,
+}
+
+impl Segment {
+    /// Parse a path of `/` separated `Primary:Secondary` glob patterns.
+    fn parse(path: &str) -> Result<Vec<Self>> {
+        let invalid = || Error::Invalid(format!("Invalid path pattern: {}", path));
+        let path = path.strip_prefix('/').unwrap_or(path);
+        path.split('/')
+            .map(|segment| {
+                if segment == "**" {
+                    return Ok(Self::AnyDepth);
+                }
+                let (primary, secondary) = segment.split_once(':').unwrap_or((segment, "*"));
+                if primary.is_empty() || secondary.is_empty() || secondary.contains(':') {
+                    Err(invalid())
+                } else {
+                    Ok(Self::Glob(primary.into(), secondary.into()))
+                }
+            })
+            .collect()
+    }
+
+    /// Check if the identifier matches the segment.
+    fn matches(&self, identifier: Identifier) -> bool {
+        match self {
+            Self::Exact(expected) => *expected == identifier,
+            Self::Glob(primary, secondary) => {
+                let (p, s) = identifier.as_ecc2();
+                glob(primary.as_bytes(), p.to_string().as_bytes())
+                    && glob(secondary.as_bytes(), s.to_string().as_bytes())
+            }
+            Self::AnyDepth => true,
+        }
+    }
+}
+
+/// Match text against a pattern where `*` matches any run of characters
+/// and `?` any single one.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    let mut retry = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                retry = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == b'?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match retry {
+                // Let the last star take one more character.
+                Some((star, from)) => {
+                    retry = Some((star, from + 1));
+                    p = star + 1;
+                    t = from + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+/// Collect the matches of the segments below the parent, the roots when
+/// there is no parent.
+fn walk<'a, T: Debug>(
+    hff: &'a Hff<T>,
+    parent: Option<TableView<'a, T>>,
+    segments: &[Segment],
+    found: &mut Matches<'a, T>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        return;
+    };
+    let children = parent.map_or_else(|| hff.tables(), |parent| parent.iter());
+
+    if let Segment::AnyDepth = segment {
+        // Match no levels, a trailing `**` matches everything below.
+        if !rest.is_empty() {
+            walk(hff, parent, rest, found);
+        } else if let Some(parent) = parent {
+            found.chunks.extend(parent.chunks());
+        }
+        // Or descend one more level and try again.
+        for child in children {
+            if rest.is_empty() {
+                found.tables.push(child);
+            }
+            walk(hff, Some(child), segments, found);
+        }
+        return;
+    }
+
+    for child in children.filter(|child| segment.matches(child.identifier())) {
+        if rest.is_empty() {
+            found.tables.push(child);
+        } else {
+            walk(hff, Some(child), rest, found);
+        }
+    }
+    if let (true, Some(parent)) = (rest.is_empty(), parent) {
+        found.chunks.extend(
+            parent
+                .chunks()
+                .filter(|chunk| segment.matches(chunk.identifier())),
+        );
+    }
+}
+
+impl<T: Debug> Hff<T> {
+    /// Find the tables and chunks at the given path of identifiers.  The
+    /// first identifier is matched against the root tables and each
+    /// following one against the children of the tables matched so far.
+    /// The last identifier also matches chunks of the tables matched by
+    /// the rest of the path.
+    pub fn find<I: Into<Identifier> + Copy>(&self, path: &[I]) -> Matches<'_, T> {
+        let segments = path
+            .iter()
+            .map(|id| Segment::Exact((*id).into()))
+            .collect::<Vec<_>>();
+        let mut found = Matches::from_indices(self, &[], &[]);
+        walk(self, None, &segments, &mut found);
+        found.sort();
+        found
+    }
+
+    /// Find the tables and chunks matching a path of Ecc2 identifiers
+    /// such as `"Root:*/Mesh:LOD?"`.  Parts are separated by `/` and each
+    /// is `Primary:Secondary` or just `Primary` to match any secondary.
+    /// `*` matches any run of characters, `?` a single character and a
+    /// part of `**` any number of tables.  Matching follows `find`.
+    pub fn glob(&self, pattern: &str) -> Result<Matches<'_, T>> {
+        let segments = Segment::parse(pattern)?;
+        let mut found = Matches::from_indices(self, &[], &[]);
+        walk(self, None, &segments, &mut found);
+        found.sort();
+        Ok(found)
+    }
+
+    /// Build an index of every path for fast repeated exact lookups.
+    pub fn path_index(&self) -> PathIndex<'_, T> {
+        PathIndex::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chunk, ChunkCache, Ecc, Header, IdType, Table};
+
+    /// A small hierarchy where every chunk holds its primary identifier:
+    ///
+    /// Test:TestSub       TRC0:TRS0 .. TRC5:TRS5
+    ///   C0Prime:C0Sub    C0C0:C0S0
+    ///     C1Prime:C1Sub  C1C0:C1S0
+    ///     C2Prime:C2Sub
+    ///       C3Prime:C3Sub  C2C0:C2S0
+    ///   C4Prime:C4Sub    C4C0:C4S0
+    fn structure() -> Hff<ChunkCache> {
+        let mut content = vec![];
+        let mut chunks = vec![];
+        let mut add = |primary: &str, secondary: &str| {
+            let chunk = Chunk::new(
+                (Ecc::new(primary), Ecc::new(secondary)),
+                primary.len() as u64,
+                content.len() as u64,
+            );
+            content.extend_from_slice(primary.as_bytes());
+            chunks.push(chunk);
+        };
+        for index in 0..6 {
+            add(&format!("TRC{}", index), &format!("TRS{}", index));
+        }
+        for (primary, secondary) in [("C0C0", "C0S0"), ("C1C0", "C1S0"), ("C2C0", "C2S0")] {
+            add(primary, secondary);
+        }
+        add("C4C0", "C4S0");
+
+        // Identifier, chunk index and count, child count and sibling offset.
+        let tables = [
+            ("Test", "TestSub", 0, 6, 2, 0),
+            ("C0Prime", "C0Sub", 6, 1, 2, 4),
+            ("C1Prime", "C1Sub", 7, 1, 0, 1),
+            ("C2Prime", "C2Sub", 0, 0, 1, 0),
+            ("C3Prime", "C3Sub", 8, 1, 0, 0),
+            ("C4Prime", "C4Sub", 9, 1, 0, 0),
+        ]
+        .into_iter()
+        .map(|(primary, secondary, index, count, children, sibling)| {
+            Table::create()
+                .identifier((Ecc::new(primary), Ecc::new(secondary)).into())
+                .chunk_index(index)
+                .chunk_count(count)
+                .child_count(children)
+                .sibling(sibling)
+                .end()
+        })
+        .collect::<Vec<_>>();
+
+        Hff::new(
+            ChunkCache::new(0, content),
+            Header::new(
+                IdType::Ecc2,
+                Ecc::new("Test"),
+                tables.len() as u32,
+                chunks.len() as u32,
+            ),
+            tables,
+            chunks,
+        )
+    }
+
+    #[test]
+    fn find() {
+        let hff = structure();
+        let root = (Ecc::new("Test"), Ecc::new("TestSub"));
+        let c0 = (Ecc::new("C0Prime"), Ecc::new("C0Sub"));
+        let c2 = (Ecc::new("C2Prime"), Ecc::new("C2Sub"));
+        let c3 = (Ecc::new("C3Prime"), Ecc::new("C3Sub"));
+
+        // Exact paths find tables and the chunks of the parent table.
+        let found = hff.find(&[root, c0, c2, c3]);
+        assert_eq!(found.tables.len(), 1);
+        assert!(found.chunks.is_empty());
+        assert_eq!(found.table().unwrap().identifier(), c3.into());
+
+        let found = hff.find(&[root, (Ecc::new("TRC1"), Ecc::new("TRS1"))]);
+        assert!(found.tables.is_empty());
+        assert_eq!(
+            hff.get_decompressed(&found.chunk().unwrap()).unwrap(),
+            b"TRC1"
+        );
+        assert!(hff.find(&[c0]).is_empty());
+        assert!(hff.find::<Identifier>(&[]).is_empty());
+
+        // Glob patterns.
+        let found = hff.glob("Test:*/C?Prime").unwrap();
+        let ids = found
+            .tables
+            .iter()
+            .map(|t| t.identifier())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![c0.into(), (Ecc::new("C4Prime"), Ecc::new("C4Sub")).into()]
+        );
+        assert_eq!(hff.glob("Test/TRC*:TRS?").unwrap().chunks.len(), 6);
+        assert_eq!(hff.glob("/Test/**/C2C0").unwrap().chunks.len(), 1);
+        assert_eq!(hff.glob("**/C3Prime/*").unwrap().chunks.len(), 1);
+        let all = hff.glob("**").unwrap();
+        assert_eq!(all.tables.len(), hff.tables_array().len());
+        assert_eq!(all.chunks.len(), hff.chunks_array().len());
+        assert!(hff.glob("Test:Other").unwrap().is_empty());
+        assert!(hff.glob("Test//C0Prime").is_err());
+
+        // The index agrees with walking the structure.
+        let index = hff.path_index();
+        assert_eq!(index.len(), all.tables.len() + all.chunks.len());
+        for path in [&[root, c0][..], &[root, c0, c2, c3], &[root, c3]] {
+            let walked = hff.find(path);
+            let indexed = index.find(path);
+            assert_eq!(
+                walked.tables.iter().map(|t| t.index()).collect::<Vec<_>>(),
+                indexed.tables.iter().map(|t| t.index()).collect::<Vec<_>>()
+            );
+        }
+        let chunk = index
+            .find(&[root, c0, (Ecc::new("C0C0"), Ecc::new("C0S0"))])
+            .chunk()
+            .unwrap();
+        assert_eq!(hff.get_decompressed(&chunk).unwrap(), b"C0C0");
+    }
+
+    #[test]
+    fn patterns() {
+        assert!(glob(b"*", b""));
+        assert!(glob(b"LOD?", b"LOD0"));
+        assert!(!glob(b"LOD?", b"LOD10"));
+        assert!(glob(b"L*D*", b"LOD10"));
+        assert!(glob(b"*Mesh", b"MeshMesh"));
+        assert!(!glob(b"Mesh", b"Mesh0"));
+
+        assert!(Segment::parse("Root:*/Mesh:LOD0").is_ok());
+        assert!(Segment::parse("/Root/**").is_ok());
+        assert!(Segment::parse("Root//Mesh").is_err());
+        assert!(Segment::parse("Root:").is_err());
+        assert!(Segment::parse("Root:A:B").is_err());
+    }
+}
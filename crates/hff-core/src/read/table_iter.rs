@@ -12,7 +12,7 @@ impl<'a, T: Debug> TableIter<'a, T> {
     pub fn new(hff: &'a Hff<T>, start: usize) -> Self {
         Self {
             hff,
-            index: Some(start),
+            index: (start < hff.tables_array().len()).then_some(start),
         }
     }
 
@@ -25,9 +25,7 @@ impl<'a, T: Debug> TableIter<'a, T> {
     pub fn children(&self) -> Self {
         if let Some(index) = &self.index {
             if self.hff.tables_array()[*index].child_count() > 0 {
-                let next = *index + 1;
-                assert!(next < self.hff.tables_array().len());
-                return Self::new(self.hff, next);
+                return Self::new(self.hff, *index + 1);
             }
         }
 
@@ -44,7 +42,7 @@ impl<'a, T: Debug> Iterator for TableIter<'a, T> {
             let result = Some(TableView::new(self.hff, index));
 
             let sibling = self.hff.tables_array()[index].sibling() as usize;
-            if sibling > 0 && index + sibling < self.hff.tables_array().len() {
+            if sibling > 0 && sibling < self.hff.tables_array().len() - index {
                 self.index = Some(index + sibling)
             }
 
@@ -1,14 +1,22 @@
-use super::{ChunkIter, Hff, TableIter};
-use crate::{ContentInfo, Identifier};
+use super::{verify::verify_content, ChunkIter, ChunkView, ContentReader, Hff, TableIter};
+use crate::{Codec, ContentInfo, Encoding, Identifier, Result};
 use std::fmt::Debug;
 
 /// View of a table.
-#[derive(Copy, Clone)]
 pub struct TableView<'a, T: Debug> {
     hff: &'a Hff<T>,
     index: usize,
 }
 
+// Views only hold a reference so they copy regardless of the accessor.
+impl<'a, T: Debug> Clone for TableView<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T: Debug> Copy for TableView<'a, T> {}
+
 impl<'a, T: Debug> Debug for TableView<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self.hff.tables_array()[self.index])
@@ -22,6 +30,20 @@ impl<'a, T: Debug> ContentInfo for TableView<'a, T> {
     fn offset(&self) -> u64 {
         self.hff.tables_array()[self.index].metadata_offset()
     }
+    fn codec(&self) -> Result<Codec> {
+        Codec::try_from(self.hff.tables_array()[self.index].metadata_codec())
+    }
+    fn uncompressed_len(&self) -> u64 {
+        let table = &self.hff.tables_array()[self.index];
+        if table.metadata_codec() == u32::from(Codec::None) {
+            table.metadata_length()
+        } else {
+            table.metadata_uncompressed_length()
+        }
+    }
+    fn encoding(&self) -> Result<Encoding> {
+        Encoding::try_from(self.hff.tables_array()[self.index].metadata_encoding())
+    }
 }
 
 impl<'a, T: Debug> TableView<'a, T> {
@@ -31,7 +53,7 @@ impl<'a, T: Debug> TableView<'a, T> {
     }
 
     /// Get the hff container we're built from.
-    pub fn hff(&self) -> &Hff<T> {
+    pub fn hff(&self) -> &'a Hff<T> {
         self.hff
     }
 
@@ -40,6 +62,11 @@ impl<'a, T: Debug> TableView<'a, T> {
         self.hff.tables_array()[self.index].metadata_length() > 0
     }
 
+    /// Get the alignment of the metadata offset.
+    pub fn metadata_alignment(&self) -> u32 {
+        self.hff.tables_array()[self.index].metadata_alignment()
+    }
+
     /// Get the current index into the tables.
     pub fn index(&self) -> usize {
         self.index
@@ -78,4 +105,37 @@ impl<'a, T: Debug> TableView<'a, T> {
     pub fn chunk_count(&self) -> usize {
         self.hff.tables_array()[self.index].chunk_count() as usize
     }
+
+    /// Find the first child table with the given identifier.
+    pub fn find_child(&self, identifier: impl Into<Identifier>) -> Option<TableView<'a, T>> {
+        let identifier = identifier.into();
+        self.iter().find(|child| child.identifier() == identifier)
+    }
+
+    /// Find the first chunk with the given identifier.
+    pub fn find_chunk(&self, identifier: impl Into<Identifier>) -> Option<ChunkView<'a, T>> {
+        let identifier = identifier.into();
+        self.chunks().find(|chunk| chunk.identifier() == identifier)
+    }
+}
+
+impl<'a, T: Debug + ContentReader> TableView<'a, T> {
+    /// Verify the metadata content against the stored checksum.
+    /// Tables without metadata or a checksum always pass.
+    pub fn verify(&self) -> bool {
+        let table = &self.hff.tables_array()[self.index];
+        !self.has_metadata()
+            || verify_content(
+                &**self.hff,
+                self,
+                table.checksum_type(),
+                table.metadata_checksum(),
+            )
+    }
+
+    /// Deserialize the metadata with the serde encoding it was written with.
+    #[cfg(feature = "serde")]
+    pub fn metadata_as<D: serde::de::DeserializeOwned>(&self) -> Result<D> {
+        self.hff.get_decoded(self)
+    }
 }
@@ -0,0 +1,262 @@
+use crate::{
+    header::FORMAT_VERSION, Chunk, Codec, Encoding, Error, Footer, Header, IdType, Result, Section,
+    Table,
+};
+
+/// Check the header describes something which can fit within a
+/// stream of the given length.  This is intended to be run before
+/// the table and chunk arrays are read so that garbage counts do
+/// not cause huge allocations.  Headers of the footer layout only
+/// get their counts from the footer so they are checked by
+/// validate_footer instead.
+pub fn validate_header(header: &Header, length: u64) -> Result<()> {
+    if header.version() != FORMAT_VERSION {
+        return Err(Error::UnsupportedVersion {
+            found: header.version(),
+            supported: FORMAT_VERSION,
+        });
+    }
+    IdType::try_from(header.id_type_value())?;
+
+    if header.has_footer() {
+        return Ok(());
+    }
+
+    let arrays = Header::SIZE as u64
+        + header.table_count() as u64 * Table::SIZE as u64
+        + header.chunk_count() as u64 * Chunk::SIZE as u64;
+    if arrays > length {
+        let tables = Header::SIZE as u64 + header.table_count() as u64 * Table::SIZE as u64;
+        let section = if tables > length {
+            Section::Tables
+        } else {
+            Section::Chunks
+        };
+        return Err(Error::Truncated { section });
+    }
+
+    Ok(())
+}
+
+/// Check that the structure read from a stream of the given length is
+/// consistent.  The table tree must be properly linked, chunk ranges
+/// must refer to existing chunks, codecs and encodings must be known
+/// and all content must be aligned as recorded and contained within the
+/// data portion of the stream.
+pub fn validate(header: &Header, tables: &[Table], chunks: &[Chunk], length: u64) -> Result<()> {
+    validate_header(header, length)?;
+    if tables.len() != header.table_count() as usize
+        || chunks.len() != header.chunk_count() as usize
+    {
+        return Err(invalid("Table or chunk count does not match the header."));
+    }
+
+    let data_start =
+        (Header::SIZE + Table::SIZE * tables.len() + Chunk::SIZE * chunks.len()) as u64;
+    validate_structure(tables, chunks, data_start, length)
+}
+
+/// Check that the footer read from the end of a stream of the given
+/// length fits the stream.  This is intended to be run before the
+/// table and chunk arrays are read so that garbage counts do not cause
+/// huge allocations.
+pub fn validate_footer_header(header: &Header, footer: &Footer, length: u64) -> Result<()> {
+    validate_header(header, length)?;
+    if !header.has_footer() {
+        return Err(invalid("The header does not use the footer layout."));
+    }
+
+    let end = footer
+        .directory_offset()
+        .checked_add(footer.arrays_size())
+        .and_then(|end| end.checked_add(Footer::SIZE as u64));
+    if footer.directory_offset() < Header::SIZE as u64 || end != Some(length) {
+        return Err(invalid(format!(
+            "{} tables and {} chunks at {} do not end a stream of {} bytes.",
+            footer.table_count(),
+            footer.chunk_count(),
+            footer.directory_offset(),
+            length
+        )));
+    }
+
+    Ok(())
+}
+
+/// Check that the structure of a footer layout stream is consistent.
+/// The same rules as validate apply except that the data lies between
+/// the header and the table array.
+pub fn validate_footer(
+    header: &Header,
+    footer: &Footer,
+    tables: &[Table],
+    chunks: &[Chunk],
+    length: u64,
+) -> Result<()> {
+    validate_footer_header(header, footer, length)?;
+    if tables.len() != footer.table_count() as usize
+        || chunks.len() != footer.chunk_count() as usize
+    {
+        return Err(invalid("Table or chunk count does not match the footer."));
+    }
+
+    validate_structure(
+        tables,
+        chunks,
+        Header::SIZE as u64,
+        footer.directory_offset(),
+    )
+}
+
+/// Check the tree, chunk ranges, codecs, encodings and content placement
+/// against the given data range.
+fn validate_structure(
+    tables: &[Table],
+    chunks: &[Chunk],
+    data_start: u64,
+    data_end: u64,
+) -> Result<()> {
+    validate_tree(tables)?;
+
+    for (index, table) in tables.iter().enumerate() {
+        let end = table.chunk_index() as u64 + table.chunk_count() as u64;
+        if table.chunk_count() > 0 && end > chunks.len() as u64 {
+            return Err(Error::OutOfBounds {
+                index: (table.chunk_index() as u64).max(chunks.len() as u64),
+            });
+        }
+        Codec::try_from(table.metadata_codec())?;
+        Encoding::try_from(table.metadata_encoding())?;
+        if table.metadata_length() > 0 {
+            validate_content(
+                "Table",
+                index,
+                table.metadata_offset(),
+                table.metadata_length(),
+                table.metadata_alignment(),
+                data_start,
+                data_end,
+            )?;
+        }
+    }
+
+    for (index, chunk) in chunks.iter().enumerate() {
+        Codec::try_from(chunk.codec())?;
+        Encoding::try_from(chunk.encoding())?;
+        validate_content(
+            "Chunk",
+            index,
+            chunk.offset(),
+            chunk.length(),
+            chunk.alignment(),
+            data_start,
+            data_end,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A table which has been visited but whose children have not all
+/// been seen yet.
+struct Open {
+    /// Index of the table.
+    index: usize,
+    /// Children still expected.
+    remaining: u32,
+    /// Whether this table is the last of its siblings, None at the
+    /// root level where that is only known from the sibling itself.
+    last: Option<bool>,
+}
+
+/// Walk the tables in order checking that the child counts and
+/// sibling offsets describe a single consistent tree.
+fn validate_tree(tables: &[Table]) -> Result<()> {
+    let mut stack: Vec<Open> = vec![];
+    for index in 0..tables.len() {
+        // Close out any tables which have seen all their children.
+        while stack.last().is_some_and(|open| open.remaining == 0) {
+            close(tables, stack.pop().unwrap(), index)?;
+        }
+
+        let last = match stack.last_mut() {
+            Some(parent) => {
+                parent.remaining -= 1;
+                Some(parent.remaining == 0)
+            }
+            None => None,
+        };
+        stack.push(Open {
+            index,
+            remaining: tables[index].child_count(),
+            last,
+        });
+    }
+
+    // Everything left ends at the end of the table array.
+    while let Some(open) = stack.pop() {
+        if open.remaining > 0 {
+            return Err(invalid(format!(
+                "Table {} is missing {} children.",
+                open.index, open.remaining
+            )));
+        }
+        close(tables, open, tables.len())?;
+    }
+
+    Ok(())
+}
+
+/// Check the sibling of a table whose subtree ends at the given index.
+fn close(tables: &[Table], open: Open, end: usize) -> Result<()> {
+    let expected = match open.last {
+        Some(true) => 0,
+        Some(false) => end - open.index,
+        None if end == tables.len() => 0,
+        None => end - open.index,
+    };
+    let sibling = tables[open.index].sibling() as usize;
+    if sibling != expected {
+        return Err(invalid(format!(
+            "Table {} has sibling {}, expected {}.",
+            open.index, sibling, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Check that content is aligned as recorded and within the data
+/// portion of the stream.
+fn validate_content(
+    kind: &str,
+    index: usize,
+    offset: u64,
+    length: u64,
+    alignment: u32,
+    data_start: u64,
+    data_end: u64,
+) -> Result<()> {
+    if !alignment.is_power_of_two() || alignment < Chunk::ALIGNMENT {
+        return Err(invalid(format!(
+            "{} {} has invalid alignment {}.",
+            kind, index, alignment
+        )));
+    }
+    if !offset.is_multiple_of(alignment as u64) {
+        return Err(invalid(format!(
+            "{} {} content offset {} is not aligned to {}.",
+            kind, index, offset, alignment
+        )));
+    }
+    match offset.checked_add(length) {
+        Some(end) if offset >= data_start && end <= data_end => Ok(()),
+        _ => Err(invalid(format!(
+            "{} {} content {}:{} is outside of the data {}..{}.",
+            kind, index, offset, length, data_start, data_end
+        ))),
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::InvalidStructure(message.into())
+}
@@ -0,0 +1,70 @@
+use crate::{Checksum, ChunkCache, ContentInfo, Identifier, Result};
+use std::{
+    borrow::Cow,
+    io::{Cursor, Read},
+};
+
+/// Uniform access to the raw content of metadata and chunks
+/// regardless of how the hff is being read.
+pub trait ContentReader {
+    /// Read the content described by the given item.
+    fn content(&self, content: &dyn ContentInfo) -> Result<Cow<'_, [u8]>>;
+
+    /// Get a reader over the content described by the given item.
+    /// The default goes through `content`, readers which can stream
+    /// from the underlying source should override this.
+    fn stream(&self, content: &dyn ContentInfo) -> Result<Box<dyn Read + '_>> {
+        Ok(match self.content(content)? {
+            Cow::Borrowed(data) => Box::new(data),
+            Cow::Owned(data) => Box::new(Cursor::new(data)),
+        })
+    }
+}
+
+impl ContentReader for ChunkCache {
+    fn content(&self, content: &dyn ContentInfo) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Borrowed(self.read(content)?))
+    }
+}
+
+/// An item which failed verification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Corrupt {
+    /// The metadata of the table at the given index.
+    Metadata {
+        /// Index of the table.
+        table: usize,
+        /// Identifier of the table.
+        identifier: Identifier,
+    },
+    /// The chunk at the given index.
+    Chunk {
+        /// Index of the chunk.
+        chunk: usize,
+        /// Identifier of the chunk.
+        identifier: Identifier,
+    },
+}
+
+/// Check the content against the stored checksum.  Content which can
+/// not be read or has an unknown checksum type is considered corrupt.
+pub(super) fn verify_content(
+    reader: &dyn ContentReader,
+    content: &dyn ContentInfo,
+    checksum_type: u32,
+    checksum: u64,
+) -> bool {
+    let checksum_type = match Checksum::try_from(checksum_type) {
+        Ok(Checksum::None) => return true,
+        Ok(checksum_type) => checksum_type,
+        Err(_) => return false,
+    };
+
+    if content.is_empty() {
+        return checksum_type.compute(&[]) == checksum;
+    }
+    match reader.content(content) {
+        Ok(data) => checksum_type.compute(&data) == checksum,
+        Err(_) => false,
+    }
+}
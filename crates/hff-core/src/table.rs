@@ -1,4 +1,4 @@
-use crate::{Identifier, Result};
+use crate::{Chunk, Identifier, Result};
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
 use std::{
     fmt::Debug,
@@ -6,7 +6,7 @@ use std::{
 };
 
 /// A table entry in the file format.
-/// Tables are 48 bytes in length when stored.
+/// Tables are 80 bytes in length when stored.
 #[repr(C, align(16))]
 #[derive(Copy, Eq, PartialEq, Clone, Hash)]
 pub struct Table {
@@ -25,6 +25,18 @@ pub struct Table {
     chunk_index: u32,
     /// The number of chunks associated with this table or zero.
     chunk_count: u32,
+    /// Checksum of the metadata content.
+    metadata_checksum: u64,
+    /// Length of the metadata once decompressed, zero if not compressed.
+    metadata_uncompressed_length: u64,
+    /// The checksum algorithm used for the metadata, see `Checksum`.
+    checksum_type: u32,
+    /// The compression codec applied to the metadata, see `Codec`.
+    metadata_codec: u32,
+    /// Alignment of the metadata offset in bytes, a power of two of at least 16.
+    metadata_alignment: u32,
+    /// The serde encoding of the metadata, see `Encoding`.
+    metadata_encoding: u32,
 }
 
 impl Debug for Table {
@@ -53,6 +65,12 @@ impl Default for Table {
             sibling: 0,
             chunk_index: 0,
             chunk_count: 0,
+            metadata_checksum: 0,
+            metadata_uncompressed_length: 0,
+            checksum_type: 0,
+            metadata_codec: 0,
+            metadata_alignment: Chunk::ALIGNMENT,
+            metadata_encoding: 0,
         }
     }
 }
@@ -92,6 +110,67 @@ impl Table {
         &mut self.metadata_offset
     }
 
+    /// Get the metadata checksum.
+    pub fn metadata_checksum(&self) -> u64 {
+        self.metadata_checksum
+    }
+
+    /// Get the metadata checksum mutably.
+    pub fn metadata_checksum_mut(&mut self) -> &mut u64 {
+        &mut self.metadata_checksum
+    }
+
+    /// Get the length of the metadata once decompressed.
+    /// Zero if the metadata is not compressed.
+    pub fn metadata_uncompressed_length(&self) -> u64 {
+        self.metadata_uncompressed_length
+    }
+
+    /// Get the metadata uncompressed length mutably.
+    pub fn metadata_uncompressed_length_mut(&mut self) -> &mut u64 {
+        &mut self.metadata_uncompressed_length
+    }
+
+    /// Get the checksum type of the metadata.
+    pub fn checksum_type(&self) -> u32 {
+        self.checksum_type
+    }
+
+    /// Get the checksum type mutably.
+    pub fn checksum_type_mut(&mut self) -> &mut u32 {
+        &mut self.checksum_type
+    }
+
+    /// Get the compression codec of the metadata.
+    pub fn metadata_codec(&self) -> u32 {
+        self.metadata_codec
+    }
+
+    /// Get the metadata codec mutably.
+    pub fn metadata_codec_mut(&mut self) -> &mut u32 {
+        &mut self.metadata_codec
+    }
+
+    /// Get the alignment of the metadata offset.
+    pub fn metadata_alignment(&self) -> u32 {
+        self.metadata_alignment
+    }
+
+    /// Get the metadata alignment mutably.
+    pub fn metadata_alignment_mut(&mut self) -> &mut u32 {
+        &mut self.metadata_alignment
+    }
+
+    /// Get the serde encoding of the metadata.
+    pub fn metadata_encoding(&self) -> u32 {
+        self.metadata_encoding
+    }
+
+    /// Get the metadata encoding mutably.
+    pub fn metadata_encoding_mut(&mut self) -> &mut u32 {
+        &mut self.metadata_encoding
+    }
+
     /// Helper to collapse tables into parents.
     pub fn offset(&mut self, data_length: u64, chunk_count: u32) {
         if self.metadata_length > 0 {
@@ -107,6 +186,11 @@ impl Table {
         self.child_count
     }
 
+    /// Get the child table count mutably.
+    pub fn child_count_mut(&mut self) -> &mut u32 {
+        &mut self.child_count
+    }
+
     /// Get the index offset from this entry to its sibling.
     /// Zero if there is no sibling.
     pub fn sibling(&self) -> u32 {
@@ -123,14 +207,24 @@ impl Table {
         self.chunk_index
     }
 
+    /// Get the chunk index mutably.
+    pub fn chunk_index_mut(&mut self) -> &mut u32 {
+        &mut self.chunk_index
+    }
+
     /// Get the number of chunks owned by this table.
     pub fn chunk_count(&self) -> u32 {
         self.chunk_count
     }
 
+    /// Get the chunk count mutably.
+    pub fn chunk_count_mut(&mut self) -> &mut u32 {
+        &mut self.chunk_count
+    }
+
     /// Read a table from the given stream.
     pub fn read<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
-        Ok(Self {
+        let table = Self {
             identifier: Identifier::new(reader.read_u128::<E>()?),
             metadata_length: reader.read_u64::<E>()?,
             metadata_offset: reader.read_u64::<E>()?,
@@ -138,7 +232,14 @@ impl Table {
             sibling: reader.read_u32::<E>()?,
             chunk_index: reader.read_u32::<E>()?,
             chunk_count: reader.read_u32::<E>()?,
-        })
+            metadata_checksum: reader.read_u64::<E>()?,
+            metadata_uncompressed_length: reader.read_u64::<E>()?,
+            checksum_type: reader.read_u32::<E>()?,
+            metadata_codec: reader.read_u32::<E>()?,
+            metadata_alignment: reader.read_u32::<E>()?,
+            metadata_encoding: reader.read_u32::<E>()?,
+        };
+        Ok(table)
     }
 
     /// Write a table to the given stream.
@@ -150,6 +251,12 @@ impl Table {
         writer.write_u32::<E>(self.sibling)?;
         writer.write_u32::<E>(self.chunk_index)?;
         writer.write_u32::<E>(self.chunk_count)?;
+        writer.write_u64::<E>(self.metadata_checksum)?;
+        writer.write_u64::<E>(self.metadata_uncompressed_length)?;
+        writer.write_u32::<E>(self.checksum_type)?;
+        writer.write_u32::<E>(self.metadata_codec)?;
+        writer.write_u32::<E>(self.metadata_alignment)?;
+        writer.write_u32::<E>(self.metadata_encoding)?;
 
         Ok(())
     }
@@ -210,6 +317,30 @@ impl TableBuilder {
         self
     }
 
+    /// Set the compression codec of the metadata.
+    pub fn metadata_codec(mut self, value: u32) -> Self {
+        self.table.metadata_codec = value;
+        self
+    }
+
+    /// Set the length of the metadata once decompressed.
+    pub fn metadata_uncompressed_length(mut self, value: u64) -> Self {
+        self.table.metadata_uncompressed_length = value;
+        self
+    }
+
+    /// Set the alignment of the metadata offset.
+    pub fn metadata_alignment(mut self, value: u32) -> Self {
+        self.table.metadata_alignment = value;
+        self
+    }
+
+    /// Set the serde encoding of the metadata.
+    pub fn metadata_encoding(mut self, value: u32) -> Self {
+        self.table.metadata_encoding = value;
+        self
+    }
+
     /// Finalize the table.
     pub fn end(self) -> Table {
         self.table
@@ -224,7 +355,7 @@ mod tests {
 
     #[test]
     fn test_layout() {
-        assert_eq!(std::mem::size_of::<Table>(), 48);
+        assert_eq!(std::mem::size_of::<Table>(), 80);
     }
 
     #[test]
@@ -237,6 +368,7 @@ mod tests {
             .sibling(4)
             .chunk_count(5)
             .chunk_index(6)
+            .metadata_alignment(4096)
             .end();
 
         assert_eq!(
@@ -249,6 +381,7 @@ mod tests {
         assert_eq!(table.sibling(), 4);
         assert_eq!(table.chunk_count(), 5);
         assert_eq!(table.chunk_index(), 6);
+        assert_eq!(table.metadata_alignment(), 4096);
     }
 
     #[test]
@@ -262,6 +395,9 @@ mod tests {
             .sibling(4)
             .chunk_count(5)
             .chunk_index(6)
+            .metadata_codec(7)
+            .metadata_uncompressed_length(8)
+            .metadata_encoding(9)
             .end();
         assert!(table.write::<crate::LE>(&mut buffer).is_ok());
 
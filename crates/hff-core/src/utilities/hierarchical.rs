@@ -1,6 +1,7 @@
-use crate::{Ecc, Endian, Error, Result, BE, LE, NE};
+use super::{read_id, take_bytes};
+use crate::{Ecc, Endian, Error, Result, Section, BE, LE};
 use byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt};
-use std::io::{Read, Write};
+use std::io::Write;
 
 /// A simple hierarchical storage system for strings.
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
@@ -16,6 +17,9 @@ pub struct Hierarchical {
 impl Hierarchical {
     /// Ecc identifier type.
     const ID: Ecc = Ecc::new("STR_HIER");
+    /// Deepest nesting accepted when reading, prevents malformed
+    /// data from exhausting the stack.
+    const MAX_DEPTH: usize = 256;
 
     /// Create a new hierarchical structure.
     pub fn new<T: Into<String>>(key: T, content: Vec<String>, children: Vec<Self>) -> Self {
@@ -109,7 +113,7 @@ impl Hierarchical {
     // Write a string into the given stream.
     fn write_string<E: ByteOrder>(value: &str, writer: &mut dyn Write) -> Result<()> {
         // Write the key value.
-        if value.len() > core::u16::MAX as usize {
+        if value.len() > u16::MAX as usize {
             return Err(Error::Invalid("String length greater than max u16!".into()));
         }
         writer.write_u16::<E>(value.len() as u16)?;
@@ -123,19 +127,22 @@ impl Hierarchical {
     /// the bytes directly..  Hmmm
     pub fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
         // Create a reader.
-        let reader: &mut dyn Read = &mut bytes;
+        let reader = &mut bytes;
 
         // Detect endian by reading in local endian.
-        let id = Ecc::from(reader.read_u64::<NE>()?);
-        match id.endian(Self::ID) {
-            Some(Endian::Big) => Ok(Self::from_reader::<BE>(reader)?),
-            Some(Endian::Little) => Ok(Self::from_reader::<LE>(reader)?),
-            None => Err(Error::Invalid("Not a valid hierarchical.".into())),
+        match read_id(reader, Self::ID)? {
+            Endian::Big => Self::from_reader::<BE>(reader, 0),
+            Endian::Little => Self::from_reader::<LE>(reader, 0),
         }
+        .map_err(|e| e.within(Section::Content))
     }
 
     // Read the hierarchy with the given endian.
-    fn from_reader<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
+    fn from_reader<E: ByteOrder>(reader: &mut &[u8], depth: usize) -> Result<Self> {
+        if depth > Self::MAX_DEPTH {
+            return Err(Error::Invalid("Hierarchical nesting is too deep.".into()));
+        }
+
         // Read the key.
         let key = Self::read_string::<E>(reader)?;
 
@@ -150,7 +157,7 @@ impl Hierarchical {
         let child_count = reader.read_u32::<E>()?;
         let mut children = vec![];
         for _ in 0..child_count {
-            children.push(Self::from_reader::<E>(reader)?);
+            children.push(Self::from_reader::<E>(reader, depth + 1)?);
         }
 
         Ok(Self {
@@ -161,18 +168,18 @@ impl Hierarchical {
     }
 
     // Read a string from the given reader.
-    fn read_string<E: ByteOrder>(reader: &mut dyn Read) -> Result<String> {
+    fn read_string<E: ByteOrder>(reader: &mut &[u8]) -> Result<String> {
         let len = reader.read_u16::<E>()?;
-        let mut buffer = vec![0; len as usize];
-        reader.read_exact(buffer.as_mut_slice())?;
+        let buffer = take_bytes(reader, len as u64)?;
 
-        Ok(std::str::from_utf8(buffer.as_slice())?.into())
+        Ok(std::str::from_utf8(buffer)?.into())
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::NE;
 
     #[test]
     fn test_serialization() {
@@ -190,4 +197,15 @@ mod test {
         let result = Hierarchical::from_bytes(bytes.as_slice()).unwrap();
         assert_eq!(test, result);
     }
+
+    #[test]
+    fn too_deep() {
+        let mut test = Hierarchical::new("leaf", vec![], vec![]);
+        for _ in 0..1000 {
+            test = Hierarchical::new("level", vec![], vec![test]);
+        }
+
+        let bytes = test.to_bytes::<NE>().unwrap();
+        assert!(Hierarchical::from_bytes(bytes.as_slice()).is_err());
+    }
 }
@@ -1,18 +1,18 @@
-use super::StringVec;
+use super::{read_id, take_bytes, StringVec};
 use crate::{
     byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt},
-    Ecc, Endian, Error, Result, BE, LE, NE,
+    Ecc, Endian, Result, Section, BE, LE,
 };
 use std::{
     collections::BTreeMap,
-    io::{copy, Read, Write},
+    io::{copy, Write},
     ops::{Deref, DerefMut},
 };
 
 /// A key + string vector container.  Basically
 /// just a key+value system except keys are strings
 /// and the values are all string vectors.
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
 pub struct Ksv {
     /// The key+value mapping.
     value_map: BTreeMap<String, StringVec>,
@@ -55,40 +55,29 @@ impl Ksv {
     /// Create a Ksv from the given bytes.
     pub fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
         // Detect the endian via the initial ID.
-        let reader: &mut dyn Read = &mut bytes;
-        // Ecc's are written as u64, so we read it back in
-        // native endian and see which endian it was actually
-        // in.  (NOTE: Symetric ID's would not work for this
-        // so don't try it if you had an id like "ssssssss"
-        // since there is no way to detect endianess.)
-        let id = Ecc::from(reader.read_u64::<NE>()?);
-        match id.endian(Self::ID) {
-            Some(endian) => match endian {
-                Endian::Big => Self::from_bytes_endian::<BE>(reader),
-                Endian::Little => Self::from_bytes_endian::<LE>(reader),
-            },
-            None => Err(Error::Invalid("Not a string vector.".into())),
+        let reader = &mut bytes;
+        match read_id(reader, Self::ID)? {
+            Endian::Big => Self::from_bytes_endian::<BE>(reader),
+            Endian::Little => Self::from_bytes_endian::<LE>(reader),
         }
+        .map_err(|e| e.within(Section::Content))
     }
 
     /// Helper to read in proper endian.
-    fn from_bytes_endian<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
+    fn from_bytes_endian<E: ByteOrder>(reader: &mut &[u8]) -> Result<Self> {
         let count = reader.read_u64::<E>()?;
         let mut result = Self::new();
         for _ in 0..count {
             // Read the key.
             let len = reader.read_u64::<E>()?;
-            let mut s = vec![0; len as usize];
-            reader.read_exact(&mut s)?;
+            let s = take_bytes(reader, len)?;
 
             // Read the string vector value.
             let len = reader.read_u64::<E>()?;
-            let mut v = vec![0; len as usize];
-            reader.read_exact(&mut v)?;
-            let v = StringVec::from_bytes(&v)?;
+            let v = StringVec::from_bytes(take_bytes(reader, len)?)?;
 
             // And put in the result.
-            result.insert(std::str::from_utf8(&s)?.to_owned(), v);
+            result.insert(std::str::from_utf8(s)?.to_owned(), v);
         }
 
         Ok(result)
@@ -126,6 +115,7 @@ impl DerefMut for Ksv {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Error;
 
     #[test]
     fn simple() {
@@ -134,10 +124,42 @@ mod tests {
             ("2", ["test", "data"].iter().into()),
             ("3", ["for", "Ksv", "containers."].iter().into()),
         ];
-        let test: Ksv = test_data.to_owned().into_iter().into();
+        let test: Ksv = test_data.iter().cloned().into();
         let bytes = test.to_bytes::<LE>().unwrap();
         let result = Ksv::from_bytes(&bytes).unwrap();
 
-        assert_eq!(Ksv::from(test_data.to_owned().into_iter()), result);
+        assert_eq!(Ksv::from(test_data.iter().cloned()), result);
+    }
+
+    #[test]
+    fn malformed() {
+        let test: Ksv = [("key", StringVec::from(["value"].iter()))]
+            .into_iter()
+            .into();
+        let mut bytes = test.to_bytes::<LE>().unwrap();
+        // Claim a huge key length.
+        bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            Ksv::from_bytes(&bytes),
+            Err(Error::Truncated {
+                section: Section::Content
+            })
+        ));
+        // Truncated content.
+        let bytes = bytes[..bytes.len() - 1].to_vec();
+        assert!(matches!(
+            Ksv::from_bytes(&bytes),
+            Err(Error::Truncated {
+                section: Section::Content
+            })
+        ));
+        // Some other utility.
+        let bytes = super::super::Hierarchical::new("key", vec![], vec![])
+            .to_bytes::<LE>()
+            .unwrap();
+        assert!(matches!(
+            Ksv::from_bytes(&bytes),
+            Err(Error::UnexpectedId { .. })
+        ));
     }
 }
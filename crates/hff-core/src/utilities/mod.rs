@@ -9,3 +9,37 @@ pub use ksv::Ksv;
 
 mod hierarchical;
 pub use hierarchical::Hierarchical;
+
+use crate::{byteorder::ReadBytesExt, Ecc, Endian, Error, Result, Section, NE};
+
+/// Split the given length off the front of the bytes.  Lengths read
+/// from the data are checked against what remains rather than being
+/// trusted for allocation.
+fn take_bytes<'a>(bytes: &mut &'a [u8], length: u64) -> Result<&'a [u8]> {
+    match usize::try_from(length) {
+        Ok(length) if length <= bytes.len() => {
+            let (head, tail) = bytes.split_at(length);
+            *bytes = tail;
+            Ok(head)
+        }
+        _ => Err(Error::Truncated {
+            section: Section::Content,
+        }),
+    }
+}
+
+/// Read the identifier at the front of the bytes and detect the endian
+/// it was written in.  Ecc's are written as u64, so reading it back in
+/// native endian shows which endian it was actually in.  (NOTE: Symetric
+/// ID's would not work for this so don't try it if you had an id like
+/// "ssssssss" since there is no way to detect endianess.)
+fn read_id(bytes: &mut &[u8], expected: Ecc) -> Result<Endian> {
+    let found = Ecc::from(
+        bytes
+            .read_u64::<NE>()
+            .map_err(|e| Error::from(e).within(Section::Content))?,
+    );
+    found
+        .endian(expected)
+        .ok_or(Error::UnexpectedId { expected, found })
+}
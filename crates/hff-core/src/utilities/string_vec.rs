@@ -1,15 +1,16 @@
+use super::{read_id, take_bytes};
 use crate::{
     byteorder::{ByteOrder, ReadBytesExt, WriteBytesExt},
-    Ecc, Endian, Error, Result, BE, LE, NE,
+    Ecc, Endian, Result, Section, BE, LE,
 };
 use std::{
-    io::{Read, Write},
+    io::Write,
     ops::{Deref, DerefMut},
 };
 
 /// A simple helper to store a vector of strings as a chunk
 /// or metadata within an hff file.
-#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Default, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct StringVec {
     /// The vector of strings.
     strings: Vec<String>,
@@ -45,32 +46,23 @@ impl StringVec {
     /// Make a string vector out of the given bytes.
     pub fn from_bytes(mut bytes: &[u8]) -> Result<Self> {
         // Detect the endian via the initial ID.
-        let reader: &mut dyn Read = &mut bytes;
-        // Ecc's are written as u64, so we read it back in
-        // native endian and see which endian it was actually
-        // in.  (NOTE: Symetric ID's would not work for this
-        // so don't try it if you had an id like "ssssssss"
-        // since there is no way to detect endianness.)
-        let id = Ecc::from(reader.read_u64::<NE>()?);
-        match id.endian(Self::ID) {
-            Some(endian) => match endian {
-                Endian::Big => Self::from_bytes_endian::<BE>(reader),
-                Endian::Little => Self::from_bytes_endian::<LE>(reader),
-            },
-            None => Err(Error::Invalid("Not a string vector.".into())),
+        let reader = &mut bytes;
+        match read_id(reader, Self::ID)? {
+            Endian::Big => Self::from_bytes_endian::<BE>(reader),
+            Endian::Little => Self::from_bytes_endian::<LE>(reader),
         }
+        .map_err(|e| e.within(Section::Content))
     }
 
     /// Helper for from bytes which deals with endian.
-    fn from_bytes_endian<E: ByteOrder>(reader: &mut dyn Read) -> Result<Self> {
+    fn from_bytes_endian<E: ByteOrder>(reader: &mut &[u8]) -> Result<Self> {
         let count = reader.read_u64::<E>()?;
 
         let mut strings = StringVec::new();
         for _ in 0..count {
             let len = reader.read_u64::<E>()?;
-            let mut s = vec![0; len as usize];
-            reader.read_exact(&mut s)?;
-            strings.push(std::str::from_utf8(&s)?.to_string());
+            let s = take_bytes(reader, len)?;
+            strings.push(std::str::from_utf8(s)?.to_string());
         }
 
         Ok(strings)
@@ -3,7 +3,7 @@ use std::ops::{Index, IndexMut};
 
 /// After flattening a table, this is where the chunks will
 /// exist.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ChunkArray {
     chunks: Vec<Chunk>,
 }
@@ -19,6 +19,11 @@ impl ChunkArray {
         self.chunks.len()
     }
 
+    /// Check if the chunk array is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
     /// Push a new chunk onto the array.
     pub fn push(&mut self, chunk: Chunk) {
         self.chunks.push(chunk);
@@ -1,5 +1,5 @@
 use super::DataSource;
-use crate::Identifier;
+use crate::{Chunk, Encoding, Identifier, Result};
 
 /// An intermediate chunk description.
 #[derive(Debug)]
@@ -8,12 +8,47 @@ pub struct ChunkDesc<'a> {
     identifier: Identifier,
     /// The source of the chunk data.
     data: DataSource<'a>,
+    /// Alignment of the chunk data, None uses the file alignment.
+    alignment: Option<u32>,
+    /// The serde encoding of the chunk data.
+    encoding: Encoding,
 }
 
 impl<'a> ChunkDesc<'a> {
     /// Create a new chunk desc.
     pub fn new(identifier: Identifier, data: DataSource<'a>) -> Self {
-        Self { identifier, data }
+        Self {
+            identifier,
+            data,
+            alignment: None,
+            encoding: Encoding::None,
+        }
+    }
+
+    /// Record the serde encoding of chunk data which was serialized
+    /// elsewhere so readers can decode it.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Get the serde encoding of the chunk data.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Align the chunk data to the given number of bytes within the
+    /// file, overriding the file alignment.  The alignment is rounded up
+    /// to a power of two of at least 16 and can't be larger than
+    /// `Chunk::MAX_ALIGNMENT`.
+    pub fn aligned(mut self, alignment: u32) -> Result<Self> {
+        self.alignment = Some(Chunk::round_alignment(alignment)?);
+        Ok(self)
+    }
+
+    /// Get the alignment of the chunk data if it overrides the file alignment.
+    pub fn alignment(&self) -> Option<u32> {
+        self.alignment
     }
 
     /// Get the chunk identifier.
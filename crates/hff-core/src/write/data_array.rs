@@ -1,43 +1,283 @@
-use super::DataSource;
-use crate::Result;
-use std::ops::{Deref, DerefMut};
+use super::{DataSource, Stream};
+use crate::{Checksum, Chunk, Error, Result};
+use std::{
+    collections::HashMap,
+    ops::{Deref, DerefMut},
+};
 
 /// Storage of data sources for writing the hff content.
 #[derive(Debug)]
 pub struct DataArray<'a> {
     /// The vector of data sources.
     data: Vec<DataSource<'a>>,
+    /// Alignment of each entry, None uses the default.
+    alignments: Vec<Option<u32>>,
+    /// The default alignment.
+    alignment: u32,
+    /// Offset of the data within the stream.
+    base: u64,
+}
+
+impl<'a> Default for DataArray<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> DataArray<'a> {
     /// Create a new empty data array.
     pub fn new() -> Self {
-        Self { data: vec![] }
+        Self {
+            data: vec![],
+            alignments: vec![],
+            alignment: Chunk::ALIGNMENT,
+            base: 0,
+        }
     }
 
     /// Push a new data source onto the array.
     pub fn push(&mut self, item: DataSource<'a>) {
+        self.push_aligned(item, None);
+    }
+
+    /// Push a new data source with the given alignment onto the array.
+    /// None uses the default alignment.
+    pub fn push_aligned(&mut self, item: DataSource<'a>, alignment: Option<u32>) {
         self.data.push(item);
+        self.alignments.push(alignment);
+    }
+
+    /// Set the alignment of entries without their own.
+    pub fn set_alignment(&mut self, alignment: u32) {
+        self.alignment = alignment;
+    }
+
+    /// Get the alignment of each entry.
+    pub fn alignments(&self) -> Vec<u32> {
+        self.alignments
+            .iter()
+            .map(|alignment| alignment.unwrap_or(self.alignment))
+            .collect()
+    }
+
+    /// Set the offset of the data within the stream.  Alignment is
+    /// relative to the start of the stream, so this must be set before
+    /// the layout is computed.
+    pub fn set_base(&mut self, base: u64) {
+        self.base = base;
+    }
+
+    /// Get the offset of the data within the stream.
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    /// Round an offset relative to the start of the data up so that it
+    /// is aligned within the stream.
+    pub fn align(base: u64, offset: u64, alignment: u32) -> u64 {
+        (base + offset).next_multiple_of(alignment as u64) - base
+    }
+
+    /// Collapse entries with identical content, keeping the first copy
+    /// of each in order with the largest alignment of its duplicates.
+    /// The entries must already be prepared.  Content
+    /// is matched on its length along with both its xxhash64 and crc32c
+    /// digests, content held in memory is also compared byte for byte.
+    /// Content read from files or other sources is matched on the
+    /// digests alone, accepting the negligible chance of different
+    /// content colliding on all three.  Returns the index of the kept
+    /// entry for each original entry so the structure can point
+    /// duplicates at the same data.
+    pub fn dedup(&mut self) -> Result<Vec<usize>> {
+        let alignments = self.alignments();
+        let mut unique: HashMap<_, Vec<usize>> = HashMap::new();
+        let mut map = Vec::with_capacity(self.data.len());
+        let mut kept: Vec<DataSource<'a>> = Vec::with_capacity(self.data.len());
+        let mut kept_alignments: Vec<u32> = Vec::with_capacity(self.data.len());
+        for (mut entry, alignment) in self.data.drain(..).zip(alignments) {
+            let length = entry.len().ok_or_else(|| {
+                Error::Invalid("Data must be prepared before deduplicating.".into())
+            })?;
+            let key = (
+                length,
+                entry.checksum(Checksum::XxHash64)?,
+                entry.checksum(Checksum::Crc32c)?,
+            );
+            let candidates = unique.entry(key).or_default();
+            let found = candidates.iter().copied().find(|&index| {
+                match (kept[index].in_memory(), entry.in_memory()) {
+                    (Some(kept), Some(entry)) => kept == entry,
+                    _ => true,
+                }
+            });
+            let index = found.unwrap_or_else(|| {
+                kept.push(entry);
+                kept_alignments.push(alignment);
+                candidates.push(kept.len() - 1);
+                kept.len() - 1
+            });
+
+            // The shared copy has to satisfy the alignment of every entry
+            // using it, alignments are powers of two so the largest does.
+            kept_alignments[index] = kept_alignments[index].max(alignment);
+            map.push(index);
+        }
+        self.data = kept;
+        self.alignments = kept_alignments.into_iter().map(Some).collect();
+        Ok(map)
+    }
+
+    /// Get the entries ready to be written.  When parallel the entries
+    /// are compressed up front using all available cores, and when dedup
+    /// is set identical content is collapsed as by `dedup`.  Returns the
+    /// index of the kept entry for each original entry.
+    pub fn compress_and_dedup(&mut self, parallel: bool, dedup: bool) -> Result<Vec<usize>> {
+        if parallel {
+            self.compress_parallel()?;
+        }
+        if dedup {
+            // Identical content can only be found once it is prepared.
+            self.prepare(Checksum::None)?;
+            self.dedup()
+        } else {
+            Ok((0..self.data.len()).collect())
+        }
+    }
+
+    /// Prepare the data in the array, using all available cores when
+    /// parallel.  See `prepare` and `prepare_parallel`.
+    pub fn prepare_with(
+        &mut self,
+        checksum: Checksum,
+        parallel: bool,
+    ) -> Result<Vec<(u64, u64, u64)>> {
+        if parallel {
+            self.prepare_parallel(checksum)
+        } else {
+            self.prepare(checksum)
+        }
+    }
+
+    /// Expand the offset, length and checksum of deduplicated entries
+    /// back out to one per original entry using the map from dedup.
+    pub fn expand(map: &[usize], offset_len: &[(u64, u64, u64)]) -> Vec<(u64, u64, u64)> {
+        map.iter().map(|index| offset_len[*index]).collect()
     }
 
     /// Prepare the data in the array.
-    pub fn prepare(&mut self) -> Result<Vec<(u64, u64)>> {
+    /// Returns the offset, length and checksum of each entry.  Offsets
+    /// are relative to the start of the data and aligned within the
+    /// stream.
+    pub fn prepare(&mut self, checksum: Checksum) -> Result<Vec<(u64, u64, u64)>> {
+        self.check_lengths()?;
+        let alignments = self.alignments();
         let mut offset_len = vec![];
         let mut offset = 0;
-        for entry in &mut self.data {
+        for (entry, alignment) in self.data.iter_mut().zip(alignments) {
             let length = if let Some(length) = entry.len() {
                 length as u64
             } else {
                 entry.prepare()?
             };
-            offset_len.push((offset, length));
+            offset = Self::align(self.base, offset, alignment);
+            offset_len.push((offset, length, entry.checksum(checksum)?));
+            offset += length;
+        }
 
-            let padding = length.next_multiple_of(16) - length;
-            offset += length + padding;
+        Ok(offset_len)
+    }
+
+    /// Prepare the data in the array using all available cores.
+    /// Entries are distributed over scoped threads, the results are
+    /// identical to `prepare` and returned in the same order.
+    pub fn prepare_parallel(&mut self, checksum: Checksum) -> Result<Vec<(u64, u64, u64)>> {
+        self.check_lengths()?;
+        let prepared =
+            self.for_each_parallel(|entry| Ok((entry.prepare()?, entry.checksum(checksum)?)))?;
+
+        let mut offset_len = vec![];
+        let mut offset = 0;
+        for ((length, checksum), alignment) in prepared.into_iter().zip(self.alignments()) {
+            offset = Self::align(self.base, offset, alignment);
+            offset_len.push((offset, length, checksum));
+            offset += length;
         }
 
         Ok(offset_len)
     }
+
+    /// Compress the entries using all available cores ahead of writers
+    /// which learn the lengths and checksums as the data is written.
+    /// Streams are left to be read while writing.
+    pub fn compress_parallel(&mut self) -> Result<()> {
+        self.for_each_parallel(|entry| {
+            if !entry.is_stream() {
+                entry.prepare()?;
+            }
+            Ok(())
+        })?;
+        Ok(())
+    }
+
+    /// Check every entry has a length which can be laid out before
+    /// writing, streams only have one once written.
+    fn check_lengths(&self) -> Result<()> {
+        if self.data.iter().any(|entry| entry.is_stream()) {
+            Err(Stream::unknown_length())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Run the work over every entry on scoped threads.  Returns the
+    /// results in the order of the entries.
+    fn for_each_parallel<R: Send>(
+        &mut self,
+        work: impl Fn(&mut DataSource<'a>) -> Result<R> + Sync,
+    ) -> Result<Vec<R>> {
+        let count = self.data.len();
+        let threads = std::thread::available_parallelism()
+            .map_or(1, |count| count.get())
+            .min(count)
+            .max(1);
+
+        // Deal the entries out round robin so large neighbors tend to
+        // land on different threads.
+        let mut buckets: Vec<Vec<(usize, &mut DataSource<'a>)>> =
+            (0..threads).map(|_| vec![]).collect();
+        for (index, entry) in self.data.iter_mut().enumerate() {
+            buckets[index % threads].push((index, entry));
+        }
+
+        let mut results: Vec<Option<R>> = (0..count).map(|_| None).collect();
+        let work = &work;
+        std::thread::scope(|scope| -> Result<()> {
+            let workers = buckets
+                .into_iter()
+                .map(|bucket| {
+                    scope.spawn(move || -> Result<Vec<(usize, R)>> {
+                        let mut results = vec![];
+                        for (index, entry) in bucket {
+                            results.push((index, work(entry)?));
+                        }
+                        Ok(results)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            for worker in workers {
+                let done = worker
+                    .join()
+                    .map_err(|_| Error::Invalid("Data preparation thread panicked.".into()))??;
+                for (index, result) in done {
+                    results[index] = Some(result);
+                }
+            }
+            Ok(())
+        })?;
+
+        Ok(results.into_iter().flatten().collect())
+    }
 }
 
 impl<'a> IntoIterator for DataArray<'a> {
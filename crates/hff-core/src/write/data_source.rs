@@ -1,7 +1,9 @@
-use crate::{Error, Result};
+use crate::{read::ContentReader, Checksum, Codec, ContentInfo, Error, Result};
 use std::{
+    borrow::Cow,
     fmt::Debug,
     fs::File,
+    io::{Read, Seek, SeekFrom},
     path::{Path, PathBuf},
 };
 
@@ -14,9 +16,28 @@ pub enum DataSource<'a> {
     Ref(&'a [u8]),
     /// An open file and the length of the data contained within it.
     File(File, u64),
-    /// A compressed chunk data source.
+    /// A file which is only opened while its content is read and the
+    /// length it had when added, so any number of files can be given
+    /// without holding them open.
+    Path(PathBuf, u64),
+    /// A range of content in another source, such as an open hff,
+    /// which is read while writing.
+    Range(ContentRange<'a>),
+    /// A stream of unknown length which is read to the end while
+    /// writing.  The length is only known once written so it can only
+    /// be written with `lazy_write`, `stream_write` or `HffWriter`.
+    Reader(Stream<'a>),
+    /// A compressed chunk data source: the codec, the level, the
+    /// uncompressed length, the source before preparation and the
+    /// compressed data after.
     #[cfg(feature = "compression")]
-    Compressed(u32, Option<Box<DataSource<'a>>>, Option<Vec<u8>>),
+    Compressed(
+        Codec,
+        u32,
+        u64,
+        Option<Box<DataSource<'a>>>,
+        Option<Vec<u8>>,
+    ),
 }
 
 impl<'a> DataSource<'a> {
@@ -35,10 +56,113 @@ impl<'a> DataSource<'a> {
         Self::File(source, len)
     }
 
-    /// Create a new compressed data source.
+    /// Create a data source for the file at the path.  The file is only
+    /// checked now, it is opened while the content is read.
+    pub fn path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let length = std::fs::metadata(&path)?.len();
+        Ok(Self::Path(path, length))
+    }
+
+    /// Open the file of a path data source, failing if it no longer has
+    /// the length it was added with.
+    pub fn open_path(path: &Path, length: u64) -> Result<File> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() == length {
+            Ok(file)
+        } else {
+            Err(Self::path_changed(path))
+        }
+    }
+
+    /// Error for a file which no longer has the length or content it was
+    /// added with.
+    pub fn path_changed(path: &Path) -> Error {
+        Error::Invalid(format!("{} changed after it was added.", path.display()))
+    }
+
+    /// Error for content which was written differently than it was
+    /// prepared, naming the file of path sources.
+    pub fn content_changed(path: Option<&Path>) -> Error {
+        match path {
+            Some(path) => Self::path_changed(path),
+            None => Error::Invalid("Content changed after it was prepared.".into()),
+        }
+    }
+
+    /// Create a data source for the given metadata or chunk of another
+    /// source.  The content is copied as stored, compressed content
+    /// keeps its codec.
+    pub fn range(
+        reader: &'a (dyn ContentReader + Sync),
+        content: &dyn ContentInfo,
+    ) -> Result<Self> {
+        Ok(Self::Range(ContentRange::new(reader, content)?))
+    }
+
+    /// Create a data source streaming the reader to its end while writing.
+    pub fn reader(source: impl Read + Send + 'a) -> Self {
+        Self::Reader(Stream(Box::new(source)))
+    }
+
+    /// Get the path of a path data source.
+    pub fn file_path(&self) -> Option<&Path> {
+        match self {
+            Self::Path(path, _) => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Get the content when it is held in memory, including compressed
+    /// content once prepared.
+    pub fn in_memory(&self) -> Option<&[u8]> {
+        match self {
+            Self::Owned(data) => Some(data),
+            Self::Ref(data) => Some(data),
+            #[cfg(feature = "compression")]
+            Self::Compressed(_, _, _, _, Some(data)) => Some(data),
+            _ => None,
+        }
+    }
+
+    /// Check if the content is a stream whose length is only known
+    /// once it has been written.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Self::Reader(_))
+    }
+
+    /// Create a new xz compressed data source.
     #[cfg(feature = "compression")]
     pub fn compressed(level: u32, source: DataSource<'a>) -> Self {
-        Self::Compressed(level, Some(Box::new(source)), None)
+        Self::compressed_with(Codec::Xz, level, source)
+    }
+
+    /// Create a new data source compressed with the given codec.
+    #[cfg(feature = "compression")]
+    pub fn compressed_with(codec: Codec, level: u32, source: DataSource<'a>) -> Self {
+        let length = source.len().unwrap_or(0) as u64;
+        Self::Compressed(codec, level, length, Some(Box::new(source)), None)
+    }
+
+    /// Get the codec the content will be stored with.
+    pub fn codec(&self) -> Codec {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Compressed(codec, _, _, _, _) => *codec,
+            Self::Range(range) => range.codec,
+            _ => Codec::None,
+        }
+    }
+
+    /// Get the length of the content before compression.
+    /// Zero if the content is not compressed.
+    pub fn uncompressed_len(&self) -> u64 {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Compressed(_, _, length, _, _) => *length,
+            Self::Range(range) if range.is_compressed() => range.uncompressed_length,
+            _ => 0,
+        }
     }
 
     /// Get the length of the content if known at this time.
@@ -47,24 +171,26 @@ impl<'a> DataSource<'a> {
             Self::Owned(d) => Some(d.len()),
             Self::Ref(d) => Some(d.len()),
             Self::File(_, l) => Some(*l as usize),
+            Self::Path(_, l) => Some(*l as usize),
+            Self::Range(range) => Some(range.length as usize),
+            Self::Reader(_) => None,
             #[cfg(feature = "compression")]
-            Self::Compressed(_, _, data) => {
-                if let Some(data) = data {
-                    Some(data.len())
-                } else {
-                    None
-                }
-            }
+            Self::Compressed(_, _, _, _, data) => data.as_ref().map(|data| data.len()),
         }
     }
 
+    /// Check if the content is known to be empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
     /// Prepare the content of the data.
-    /// For compressed content this runs the default compression
-    /// and turns this into an Owned variant.
+    /// For compressed content this runs the compression codec and
+    /// stores the result for writing.
     pub fn prepare(&mut self) -> Result<u64> {
         match self {
             #[cfg(feature = "compression")]
-            Self::Compressed(level, source, data) => {
+            Self::Compressed(codec, level, _, source, data) => {
                 // Take the source item and collapse it into the owned data entry
                 // if needed.  (Prepare is re-entrant and could be called several
                 // times.)
@@ -73,14 +199,35 @@ impl<'a> DataSource<'a> {
                     match source {
                         DataSource::Owned(d) => *data = Some(d),
                         DataSource::Ref(d) => *data = Some(d.into()),
-                        DataSource::File(mut f, _) => {
+                        DataSource::File(f, length) => {
                             let mut buffer = vec![];
-                            std::io::copy(&mut f, &mut buffer)?;
+                            f.take(length).read_to_end(&mut buffer)?;
+                            if buffer.len() as u64 != length {
+                                return Err(DataSource::content_changed(None));
+                            }
                             *data = Some(buffer);
                         }
-                        _ => panic!(
-                            "Invalid data source.  Compressing compressed data for instance."
-                        ),
+                        DataSource::Path(path, length) => {
+                            let mut buffer = vec![];
+                            Self::open_path(&path, length)?
+                                .take(length)
+                                .read_to_end(&mut buffer)?;
+                            if buffer.len() as u64 != length {
+                                return Err(DataSource::path_changed(&path));
+                            }
+                            *data = Some(buffer);
+                        }
+                        DataSource::Range(range) if !range.is_compressed() => {
+                            let mut buffer = vec![];
+                            std::io::copy(&mut range.stream()?, &mut buffer)?;
+                            *data = Some(buffer);
+                        }
+                        DataSource::Reader(_) => return Err(Stream::unknown_length()),
+                        DataSource::Range(_) | DataSource::Compressed(..) => {
+                            return Err(Error::Invalid(
+                                "Compressed content can't be compressed again.".into(),
+                            ))
+                        }
                     }
                 } else {
                     // Already prepared or internal error.
@@ -94,17 +241,179 @@ impl<'a> DataSource<'a> {
                 };
 
                 // Compress it and replace data.
-                let mut encoder = xz2::write::XzEncoder::new(vec![], (*level).min(9));
                 let source = data.take().unwrap();
-                std::io::copy(&mut source.as_slice(), &mut encoder)?;
-                *data = Some(encoder.finish()?);
+                *data = Some(codec.compress(*level, &source)?);
 
                 Ok(data.as_ref().unwrap().len() as u64)
             }
+            Self::Reader(_) => Err(Stream::unknown_length()),
             // Everything else can be used as is.
             _ => Ok(self.len().unwrap() as u64),
         }
     }
+
+    /// Compute the checksum of the prepared content.
+    /// File content is read and the file is returned to its prior
+    /// position so it can still be written afterwards.
+    pub fn checksum(&mut self, checksum: Checksum) -> Result<u64> {
+        if checksum == Checksum::None {
+            return Ok(0);
+        }
+
+        match self {
+            Self::Owned(data) => Ok(checksum.compute(data)),
+            Self::Ref(data) => Ok(checksum.compute(data)),
+            Self::File(file, length) => {
+                let position = file.stream_position()?;
+                let result = hash_exact(file, *length, checksum)?;
+                file.seek(SeekFrom::Start(position))?;
+                Ok(result)
+            }
+            Self::Reader(_) => Err(Stream::unknown_length()),
+            Self::Path(path, length) => {
+                hash_exact(&mut Self::open_path(path, *length)?, *length, checksum)
+            }
+            Self::Range(range) => {
+                let mut stream = range.stream()?;
+                let mut hasher = checksum.hasher();
+                let mut buffer = vec![0; 64 * 1024];
+                loop {
+                    let count = stream.read(&mut buffer)?;
+                    if count == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..count]);
+                }
+                Ok(hasher.finish())
+            }
+            #[cfg(feature = "compression")]
+            Self::Compressed(_, _, _, _, data) => match data {
+                Some(data) => Ok(checksum.compute(data)),
+                None => Err(Error::Invalid(
+                    "Compressed data must be prepared before computing the checksum.".into(),
+                )),
+            },
+        }
+    }
+}
+
+/// Hash exactly the length of content from the reader.
+fn hash_exact(reader: &mut dyn Read, length: u64, checksum: Checksum) -> Result<u64> {
+    let mut hasher = checksum.hasher();
+    let mut remaining = length;
+    let mut buffer = vec![0; 64 * 1024];
+    while remaining > 0 {
+        let count = remaining.min(buffer.len() as u64) as usize;
+        reader.read_exact(&mut buffer[..count])?;
+        hasher.update(&buffer[..count]);
+        remaining -= count as u64;
+    }
+    Ok(hasher.finish())
+}
+
+/// A range of content in another source along with how it is stored.
+/// The reader is shared, so content from a single hff can be spread
+/// over any number of data sources.
+pub struct ContentRange<'a> {
+    /// The source of the content.
+    reader: &'a (dyn ContentReader + Sync),
+    /// Offset of the content in the source.
+    offset: u64,
+    /// Length of the content in the source.
+    length: u64,
+    /// The codec the content is compressed with.
+    codec: Codec,
+    /// Length of the content once decompressed.
+    uncompressed_length: u64,
+}
+
+impl<'a> Debug for ContentRange<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "ContentRange({}:{}, {:?})",
+            self.offset, self.length, self.codec
+        )
+    }
+}
+
+impl<'a> ContentInfo for ContentRange<'a> {
+    fn len(&self) -> u64 {
+        self.length
+    }
+    fn offset(&self) -> u64 {
+        self.offset
+    }
+    fn codec(&self) -> Result<Codec> {
+        Ok(self.codec)
+    }
+    fn uncompressed_len(&self) -> u64 {
+        self.uncompressed_length
+    }
+}
+
+impl<'a> ContentRange<'a> {
+    /// Create a range covering the given content of the reader.
+    pub fn new(reader: &'a (dyn ContentReader + Sync), content: &dyn ContentInfo) -> Result<Self> {
+        Ok(Self {
+            reader,
+            offset: content.offset(),
+            length: content.len(),
+            codec: content.codec()?,
+            uncompressed_length: content.uncompressed_len(),
+        })
+    }
+
+    /// Check if the content is stored compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.codec != Codec::None
+    }
+
+    /// Get a reader over the content as stored.
+    pub fn stream(&self) -> Result<Box<dyn Read + 'a>> {
+        self.reader.stream(self)
+    }
+
+    /// Read part of the content as stored, for writers which can't hold
+    /// a stream between reads.
+    pub fn read_block(&self, position: u64, length: u64) -> Result<Cow<'a, [u8]>> {
+        let length = length.min(self.length.saturating_sub(position));
+        self.reader.content(&(length, self.offset + position))
+    }
+}
+
+/// A boxed reader of content with an unknown length.
+pub struct Stream<'a>(Box<dyn Read + Send + 'a>);
+
+impl<'a> Debug for Stream<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Stream")
+    }
+}
+
+impl<'a> Read for Stream<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<'a> Stream<'a> {
+    /// Error for operations which need the length up front.
+    pub fn unknown_length() -> Error {
+        Error::Invalid(
+            "The length of reader content is only known once written, \
+             write it with lazy_write or stream_write and without deduplication."
+                .into(),
+        )
+    }
+}
+
+impl<'a> TryInto<DataSource<'a>> for Box<dyn Read + Send + 'a> {
+    type Error = Error;
+
+    fn try_into(self) -> std::prelude::v1::Result<DataSource<'a>, Self::Error> {
+        Ok(DataSource::Reader(Stream(self)))
+    }
 }
 
 impl<'a> TryInto<DataSource<'a>> for &str {
@@ -143,9 +452,7 @@ impl<'a> TryInto<DataSource<'a>> for &Path {
     type Error = Error;
 
     fn try_into(self) -> std::prelude::v1::Result<DataSource<'a>, Self::Error> {
-        let file = std::fs::File::open(self)?;
-        let length = file.metadata()?.len();
-        Ok(DataSource::File(file, length))
+        DataSource::path(self)
     }
 }
 
@@ -153,9 +460,7 @@ impl<'a> TryInto<DataSource<'a>> for PathBuf {
     type Error = Error;
 
     fn try_into(self) -> std::prelude::v1::Result<DataSource<'a>, Self::Error> {
-        let file = std::fs::File::open(&self)?;
-        let length = file.metadata()?.len();
-        Ok(DataSource::File(file, length))
+        DataSource::path(self)
     }
 }
 
@@ -169,10 +474,58 @@ where
     type Error = Error;
 
     fn try_into(self) -> std::prelude::v1::Result<DataSource<'a>, Self::Error> {
-        Ok(DataSource::Compressed(
+        Ok(DataSource::compressed(self.0, self.1.try_into()?))
+    }
+}
+
+#[cfg(feature = "compression")]
+impl<'a, T> TryInto<DataSource<'a>> for (Codec, u32, T)
+where
+    T: TryInto<DataSource<'a>>,
+    <T as TryInto<DataSource<'a>>>::Error: std::fmt::Debug,
+    Error: From<<T as TryInto<DataSource<'a>>>::Error>,
+{
+    type Error = Error;
+
+    fn try_into(self) -> std::prelude::v1::Result<DataSource<'a>, Self::Error> {
+        Ok(DataSource::compressed_with(
             self.0,
-            Some(Box::new(self.1.try_into()?)),
-            None,
+            self.1,
+            self.2.try_into()?,
         ))
     }
 }
+
+#[cfg(all(test, feature = "compression"))]
+mod tests {
+    use super::*;
+    use crate::{
+        read::{ChunkView, Hff},
+        Chunk, ChunkCache, Ecc, Header, IdType,
+    };
+
+    #[test]
+    fn compress_compressed() {
+        // Nested compression is reported rather than attempted.
+        let mut nested =
+            DataSource::compressed(6, DataSource::compressed(6, "Nested".try_into().unwrap()));
+        assert!(matches!(nested.prepare(), Err(Error::Invalid(_))));
+
+        // As is a range over content which is already compressed.
+        let content = "Compressed content.".as_bytes();
+        let data = Codec::Xz.compress(6, content).unwrap();
+        let mut chunk = Chunk::new((Ecc::new("Packed"), Ecc::INVALID), data.len() as u64, 0);
+        *chunk.codec_mut() = Codec::Xz.into();
+        *chunk.uncompressed_length_mut() = content.len() as u64;
+        let hff = Hff::new(
+            ChunkCache::new(0, data.clone()),
+            Header::new(IdType::Ecc2, Ecc::new("Test"), 0, 1),
+            vec![],
+            vec![chunk],
+        );
+        let reader = ChunkCache::new(0, data);
+        let range = DataSource::range(&reader, &ChunkView::new(&hff, 0)).unwrap();
+        let mut compressed = DataSource::compressed_with(Codec::Xz, 6, range);
+        assert!(matches!(compressed.prepare(), Err(Error::Invalid(_))));
+    }
+}
@@ -1,5 +1,5 @@
 use super::{ChunkArray, DataArray, TableArray};
-use crate::{Chunk, Header, Table};
+use crate::{read::DedupStats, Checksum, Chunk, Header, Result, Table};
 
 /// Description of hff and content.
 #[derive(Debug)]
@@ -10,6 +10,12 @@ pub struct HffDesc<'a> {
     chunks: ChunkArray,
     /// The data blob.
     data: Option<DataArray<'a>>,
+    /// The checksum to compute for metadata and chunks.
+    checksum: Checksum,
+    /// Prepare the data on multiple threads.
+    parallel: bool,
+    /// Store identical content only once.
+    dedup: bool,
 }
 
 impl<'a> HffDesc<'a> {
@@ -19,53 +25,132 @@ impl<'a> HffDesc<'a> {
             tables,
             chunks,
             data: Some(data),
+            checksum: Checksum::None,
+            parallel: false,
+            dedup: false,
         }
     }
 
-    /// Finish the descriptor and return the component parts.
+    /// Compute and store the given checksum for all metadata and chunks.
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Get the checksum which will be computed.
+    pub fn checksum(&self) -> Checksum {
+        self.checksum
+    }
+
+    /// Compress and checksum the data on all available cores before
+    /// writing.  The output is identical to the single threaded write,
+    /// but during a lazy write compressed content is then held in memory
+    /// rather than being compressed directly into the stream.
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Check if the data will be prepared in parallel.
+    pub fn parallel(&self) -> bool {
+        self.parallel
+    }
+
+    /// Store identical metadata and chunk content only once, pointing
+    /// every entry with the same content at a single copy.  All content
+    /// is prepared up front to find the duplicates so during a lazy write
+    /// compressed content is held in memory.
+    pub fn with_dedup(mut self, dedup: bool) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    /// Check if identical content will be stored once.
+    pub fn dedup(&self) -> bool {
+        self.dedup
+    }
+
+    /// Align metadata and chunk data to the given number of bytes within
+    /// the file.  Chunks with their own alignment keep it.  The alignment
+    /// is rounded up to a power of two of at least 16, the default, and
+    /// can't be larger than `Chunk::MAX_ALIGNMENT`.
+    pub fn with_alignment(mut self, alignment: u32) -> Result<Self> {
+        let alignment = Chunk::round_alignment(alignment)?;
+        if let Some(data) = &mut self.data {
+            data.set_alignment(alignment);
+        }
+        Ok(self)
+    }
+
+    /// Finish the descriptor and return the component parts.  The
+    /// alignment of each entry is recorded and the data is based at the
+    /// end of the arrays, writers using another layout must rebase it.
     pub fn finish(self) -> (TableArray, ChunkArray, DataArray<'a>) {
-        (self.tables, self.chunks, self.data.unwrap())
+        let offset_to_blob = self.offset_to_blob() as u64;
+        let (mut tables, mut chunks, mut data) = (self.tables, self.chunks, self.data.unwrap());
+        data.set_base(offset_to_blob);
+
+        for (slot, alignment) in Self::slots(&tables).into_iter().zip(data.alignments()) {
+            match slot {
+                Slot::Metadata(index) => *tables[index].1.metadata_alignment_mut() = alignment,
+                Slot::Chunk(index) => *chunks[index].alignment_mut() = alignment,
+            }
+        }
+
+        (tables, chunks, data)
     }
 
-    /// Update tables and chunks for the given offset and length data.
+    /// Update tables and chunks for the given offset, length and checksum data.
+    /// Returns how much of the content is shared between entries.
     pub fn update_data(
         tables: &mut TableArray,
         chunks: &mut ChunkArray,
         offset: u64,
-        offset_len: &[(u64, u64)],
-    ) {
-        let mut table_index = 0;
-        let mut chunk_index = 0;
-        let mut chunk_count = 0;
-        let mut entry = 0;
-
-        loop {
-            if chunk_count > 0 {
-                // We are filling in chunks.
-                *chunks[chunk_index].offset_mut() = offset_len[entry].0 + offset;
-                *chunks[chunk_index].length_mut() = offset_len[entry].1;
-
-                chunk_count -= 1;
-                entry += 1;
-                chunk_index += 1;
-                continue;
+        checksum: Checksum,
+        offset_len: &[(u64, u64, u64)],
+    ) -> DedupStats {
+        for (slot, entry) in Self::slots(tables).into_iter().zip(offset_len) {
+            match slot {
+                Slot::Metadata(index) => {
+                    let table = &mut tables[index].1;
+                    *table.metadata_offset_mut() = entry.0 + offset;
+                    *table.metadata_length_mut() = entry.1;
+                    *table.metadata_checksum_mut() = entry.2;
+                    *table.checksum_type_mut() = checksum.into();
+                }
+                Slot::Chunk(index) => {
+                    let chunk = &mut chunks[index];
+                    *chunk.offset_mut() = entry.0 + offset;
+                    *chunk.length_mut() = entry.1;
+                    *chunk.checksum_mut() = entry.2;
+                    *chunk.checksum_type_mut() = checksum.into();
+                }
             }
+        }
 
-            if table_index == tables.len() {
-                break;
-            }
+        DedupStats::from_content(
+            offset_len
+                .iter()
+                .map(|(offset, length, _)| (*offset, *length)),
+        )
+    }
 
-            if tables[table_index].0 {
-                // We have metadata in this table.
-                *tables[table_index].1.metadata_offset_mut() = offset_len[entry].0 + offset;
-                *tables[table_index].1.metadata_length_mut() = offset_len[entry].1;
-                entry += 1;
+    /// The metadata and chunks in the order of the data array entries.
+    fn slots(tables: &TableArray) -> Vec<Slot> {
+        let mut slots = vec![];
+        let mut chunk_index = 0;
+        for index in 0..tables.len() {
+            if tables[index].0 {
+                slots.push(Slot::Metadata(index));
+            }
+            for _ in 0..tables[index].1.chunk_count() {
+                slots.push(Slot::Chunk(chunk_index));
+                chunk_index += 1;
             }
-
-            chunk_count = tables[table_index].1.chunk_count();
-            table_index += 1;
         }
+        slots
     }
+
     /// Size of the content arrays.
     pub fn arrays_size(&self) -> usize {
         self.tables.len() * Table::SIZE + self.chunks.len() * Chunk::SIZE
@@ -77,3 +162,11 @@ impl<'a> HffDesc<'a> {
         Header::SIZE + self.arrays_size()
     }
 }
+
+/// Where a data array entry belongs in the structure.
+enum Slot {
+    /// Metadata of the table at the index.
+    Metadata(usize),
+    /// The chunk at the index.
+    Chunk(usize),
+}
@@ -4,7 +4,7 @@ mod chunk_array;
 pub use chunk_array::ChunkArray;
 
 mod data_source;
-pub use data_source::DataSource;
+pub use data_source::{ContentRange, DataSource, Stream};
 
 mod table_array;
 pub use table_array::TableArray;
@@ -24,7 +24,11 @@ pub use table_desc::TableDesc;
 mod hff_desc;
 pub use hff_desc::HffDesc;
 
-use crate::{Error, Identifier, Result};
+use crate::{
+    read::{ContentReader, TableView},
+    Chunk, ContentInfo, Error, Identifier, Result,
+};
+use std::fmt::Debug;
 
 /// Start building a new table.
 pub fn table<'a>(identifier: impl Into<Identifier>) -> TableBuilder<'a> {
@@ -39,6 +43,50 @@ where
     Ok(ChunkDesc::new(identifier.into(), content.try_into()?))
 }
 
+/// Build a new chunk from the value serialized with the given encoding.
+/// The encoding is recorded so readers can decode it.
+#[cfg(feature = "serde")]
+pub fn chunk_serde<'a, T: serde::Serialize + ?Sized>(
+    identifier: impl Into<Identifier>,
+    value: &T,
+    encoding: crate::Encoding,
+) -> Result<ChunkDesc<'a>> {
+    let data = DataSource::Owned(encoding.encode(value)?);
+    Ok(ChunkDesc::new(identifier.into(), data).with_encoding(encoding))
+}
+
+/// Copy a table along with its metadata, chunks and child tables from
+/// an open hff.  No content is read until the new hff is written, it is
+/// then streamed from the source as stored, keeping compression, serde
+/// encodings and chunk alignments above the default.
+pub fn graft<'a, T: Debug + ContentReader + Sync>(
+    source: &TableView<'a, T>,
+) -> Result<TableBuilder<'a>> {
+    let reader: &'a T = source.hff();
+    let mut table = table(source.identifier());
+    if source.has_metadata() {
+        table = table
+            .metadata_source(DataSource::range(reader, source)?)
+            .with_metadata_encoding(source.encoding()?);
+    }
+
+    let mut chunks = vec![];
+    for chunk in source.chunks() {
+        let mut desc = ChunkDesc::new(chunk.identifier(), DataSource::range(reader, &chunk)?)
+            .with_encoding(chunk.encoding()?);
+        if chunk.alignment() > Chunk::ALIGNMENT {
+            desc = desc.aligned(chunk.alignment())?;
+        }
+        chunks.push(desc);
+    }
+
+    let children = source
+        .iter()
+        .map(|child| graft(&child))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(table.chunks(chunks).children(children))
+}
+
 /// Build the structure of the Hff content.
 pub fn hff<'a>(tables: impl IntoIterator<Item = TableBuilder<'a>>) -> HffDesc<'a> {
     // Split the tables into their components.
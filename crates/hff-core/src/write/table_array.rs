@@ -2,7 +2,7 @@ use crate::{ByteOrder, Result, Table};
 use std::ops::{Index, IndexMut};
 
 /// The table array to be written.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct TableArray {
     /// A flag indicating if the table had attached metadata,
     /// and the table structure itself.
@@ -20,6 +20,11 @@ impl TableArray {
         self.tables.len()
     }
 
+    /// Check if the table array is empty.
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
     /// Push a new table into the array.
     pub fn push(&mut self, has_metadata: bool, table: Table) {
         self.tables.push((has_metadata, table));
@@ -1,5 +1,5 @@
 use super::{ChunkDesc, DataSource, TableDesc};
-use crate::{Error, Identifier, Result};
+use crate::{Encoding, Error, Identifier, Result};
 
 /// Builder for tables.
 #[derive(Debug)]
@@ -8,6 +8,8 @@ pub struct TableBuilder<'a> {
     identifier: Identifier,
     /// Optional metadata associated with the table.
     metadata: Option<DataSource<'a>>,
+    /// The serde encoding of the metadata.
+    metadata_encoding: Encoding,
     /// Chunks associated with the table..
     chunks: Vec<ChunkDesc<'a>>,
     /// Child tables under this table.
@@ -20,6 +22,7 @@ impl<'a> TableBuilder<'a> {
         Self {
             identifier,
             metadata: None,
+            metadata_encoding: Encoding::None,
             chunks: vec![],
             children: vec![],
         }
@@ -31,6 +34,34 @@ impl<'a> TableBuilder<'a> {
         T: TryInto<DataSource<'a>, Error = Error>,
     {
         self.metadata = Some(content.try_into()?);
+        self.metadata_encoding = Encoding::None;
+        Ok(self)
+    }
+
+    /// Set the metadata for this table from a data source.
+    pub fn metadata_source(mut self, source: DataSource<'a>) -> Self {
+        self.metadata = Some(source);
+        self.metadata_encoding = Encoding::None;
+        self
+    }
+
+    /// Record the serde encoding of metadata which was serialized
+    /// elsewhere so readers can decode it.
+    pub fn with_metadata_encoding(mut self, encoding: Encoding) -> Self {
+        self.metadata_encoding = encoding;
+        self
+    }
+
+    /// Set the metadata for this table to the value serialized with the
+    /// given encoding.  The encoding is recorded so readers can decode it.
+    #[cfg(feature = "serde")]
+    pub fn metadata_serde<T: serde::Serialize + ?Sized>(
+        mut self,
+        value: &T,
+        encoding: Encoding,
+    ) -> Result<Self> {
+        self.metadata = Some(DataSource::Owned(encoding.encode(value)?));
+        self.metadata_encoding = encoding;
         Ok(self)
     }
 
@@ -57,6 +88,7 @@ impl<'a> TableBuilder<'a> {
                 .map(|desc| desc.finish())
                 .collect(),
         )
+        .with_metadata_encoding(self.metadata_encoding)
     }
 
     /// Get the identifier.
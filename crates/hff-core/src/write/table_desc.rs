@@ -1,5 +1,5 @@
 use super::{ChunkArray, ChunkDesc, DataArray, DataSource, TableArray};
-use crate::{Chunk, Identifier, Table};
+use crate::{Chunk, Codec, Encoding, Identifier, Table};
 
 /// Description of a table.
 #[derive(Debug)]
@@ -8,6 +8,8 @@ pub struct TableDesc<'a> {
     identifier: Identifier,
     /// The metadata for the table.
     metadata: Option<DataSource<'a>>,
+    /// The serde encoding of the metadata.
+    metadata_encoding: Encoding,
     /// The chunks attached to the table.
     chunks: Vec<ChunkDesc<'a>>,
     /// The child tables.
@@ -25,11 +27,18 @@ impl<'a> TableDesc<'a> {
         Self {
             identifier,
             metadata,
+            metadata_encoding: Encoding::None,
             chunks,
             children,
         }
     }
 
+    /// Set the serde encoding of the metadata.
+    pub fn with_metadata_encoding(mut self, encoding: Encoding) -> Self {
+        self.metadata_encoding = encoding;
+        self
+    }
+
     /// Flatten the description into separate portions of tables,
     /// chunks and the data blob.
     pub fn flatten(
@@ -41,11 +50,13 @@ impl<'a> TableDesc<'a> {
     ) {
         // First, record if the table had metadata and push that to the
         // data array if so.
-        let had_metadata = if let Some(metadata) = self.metadata {
+        let (had_metadata, metadata_codec, metadata_length) = if let Some(metadata) = self.metadata
+        {
+            let (codec, length) = (metadata.codec(), metadata.uncompressed_len());
             data.push(metadata);
-            true
+            (true, codec, length)
         } else {
-            false
+            (false, Codec::None, 0)
         };
 
         // Record the start of the chunks and how many there are.
@@ -55,8 +66,14 @@ impl<'a> TableDesc<'a> {
         // Second, push the chunks for this table into the chunk and data arrays.
         for chunk in self.chunks {
             // Push without offset/length, we don't know them at this time.
-            chunks.push(Chunk::new(chunk.identifier(), 0, 0));
-            data.push(chunk.data_source());
+            let mut entry = Chunk::new(chunk.identifier(), 0, 0);
+            let alignment = chunk.alignment();
+            *entry.encoding_mut() = chunk.encoding().into();
+            let source = chunk.data_source();
+            *entry.codec_mut() = source.codec().into();
+            *entry.uncompressed_length_mut() = source.uncompressed_len();
+            chunks.push(entry);
+            data.push_aligned(source, alignment);
         }
 
         // Record how many tables there are so we can fix up the sibling
@@ -75,6 +92,9 @@ impl<'a> TableDesc<'a> {
                 })
                 .chunk_count(chunk_count as u32)
                 .child_count(self.children.len() as u32)
+                .metadata_codec(metadata_codec.into())
+                .metadata_uncompressed_length(metadata_length)
+                .metadata_encoding(self.metadata_encoding.into())
                 .end(),
         );
 
@@ -0,0 +1,304 @@
+//! Derive support for mapping Rust structs onto HFF tables.
+#![warn(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Ident, LitStr, Path};
+
+/// Derive `HffTable` for a struct with named fields.
+///
+/// The struct is given its table identifier with `#[hff(id = "Primary")]`
+/// or `#[hff(id = "Primary:Secondary")]`, each part being at most eight
+/// bytes.  Every field maps onto part of the table:
+///
+/// - `#[hff(metadata)]` stores the field as the table metadata.
+/// - `#[hff(id = "...")]` stores the field as a chunk with the identifier.
+/// - `#[hff(child)]` stores the field as a child table, the field type
+///   must implement `HffTable`.  It is read from the first child with
+///   the identifier of the type.
+/// - `#[hff(children)]` stores each element of a `Vec` as a child table,
+///   all children with the identifier of the element type are read.
+/// - `#[hff(skip)]` leaves the field out, it is `Default` when read.
+///
+/// Metadata and chunk fields implement `HffContent` unless they are given
+/// a serde encoding with `encoding = "bincode" | "cbor" | "json" |
+/// "msgpack"`, which requires the `serde` feature.  The generated code
+/// refers to `hff_core` through the `hff` facade when the crate depends
+/// on it, renamed or not, and through `hff-std` otherwise.  The path can
+/// be given with `#[hff(crate = "path")]` on the struct, for instance
+/// when hff is only reached through another crate.
+#[proc_macro_derive(HffTable, attributes(hff))]
+pub fn derive_hff_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// How a field maps onto the table.
+enum Kind {
+    /// The table metadata.
+    Metadata,
+    /// A chunk with the identifier.
+    Chunk(LitStr),
+    /// A single child table.
+    Child,
+    /// A child table for each element.
+    Children,
+    /// Not stored.
+    Skip,
+}
+
+/// The parsed attributes of a field.
+struct FieldAttr {
+    /// How the field is stored.
+    kind: Kind,
+    /// The serde encoding variant if any.
+    encoding: Option<Ident>,
+}
+
+/// Generate the HffTable implementation.
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (id, krate) = struct_attrs(&input)?;
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(unsupported(&input)),
+        },
+        _ => return Err(unsupported(&input)),
+    };
+
+    let mut write = vec![];
+    let mut read = vec![];
+    for field in fields {
+        let name = field.ident.as_ref().unwrap();
+        let attr = field_attr(&field.attrs, name)?;
+        let (store, load) = match (&attr.kind, &attr.encoding) {
+            (Kind::Metadata, None) => (
+                quote! { table = table.metadata_source(#krate::HffContent::to_source(&self.#name)?); },
+                quote! { #krate::HffContent::read(table.hff(), table)? },
+            ),
+            (Kind::Metadata, Some(encoding)) => (
+                quote! { table = table.metadata_serde(&self.#name, #krate::Encoding::#encoding)?; },
+                quote! { table.metadata_as()? },
+            ),
+            (Kind::Chunk(chunk_id), encoding) => {
+                let chunk_id = identifier(&krate, chunk_id)?;
+                let store = match encoding {
+                    None => quote! {
+                        chunks.push(#krate::write::ChunkDesc::new(
+                            #krate::Identifier::from(#chunk_id),
+                            #krate::HffContent::to_source(&self.#name)?,
+                        ));
+                    },
+                    Some(encoding) => quote! {
+                        chunks.push(#krate::write::chunk_serde(
+                            #chunk_id,
+                            &self.#name,
+                            #krate::Encoding::#encoding,
+                        )?);
+                    },
+                };
+                let decode = match encoding {
+                    None => quote! { #krate::HffContent::read(chunk.hff(), &chunk)? },
+                    Some(_) => quote! { chunk.decode()? },
+                };
+                let load = quote! {{
+                    let chunk = table.find_chunk(#chunk_id).ok_or_else(|| {
+                        #krate::Error::NotFound(::std::format!(
+                            "Missing chunk {:?} in {:?}.",
+                            #krate::Identifier::from(#chunk_id),
+                            table.identifier()
+                        ))
+                    })?;
+                    #decode
+                }};
+                (store, load)
+            }
+            (Kind::Child, None) => (
+                quote! { children.push(#krate::HffTable::to_table(&self.#name)?); },
+                quote! { #krate::HffTable::from_child(table)? },
+            ),
+            (Kind::Children, None) => (
+                quote! {
+                    for child in &self.#name {
+                        children.push(#krate::HffTable::to_table(child)?);
+                    }
+                },
+                quote! { #krate::HffTable::from_children(table)? },
+            ),
+            (Kind::Skip, None) => (quote! {}, quote! { ::std::default::Default::default() }),
+            (_, Some(encoding)) => {
+                return Err(syn::Error::new_spanned(
+                    encoding,
+                    "Only metadata and chunks can have an encoding.",
+                ))
+            }
+        };
+        write.push(store);
+        read.push(quote! { #name: #load });
+    }
+
+    let ty = &input.ident;
+    let id = identifier(&krate, &id)?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::HffTable for #ty #ty_generics #where_clause {
+            fn identifier() -> #krate::Identifier {
+                #krate::Identifier::from(#id)
+            }
+
+            fn to_table(&self) -> #krate::Result<#krate::write::TableBuilder<'_>> {
+                #[allow(unused_mut)]
+                let mut table = #krate::write::table(
+                    <Self as #krate::HffTable>::identifier()
+                );
+                #[allow(unused_mut)]
+                let mut chunks = ::std::vec::Vec::new();
+                #[allow(unused_mut)]
+                let mut children = ::std::vec::Vec::new();
+                #(#write)*
+                ::std::result::Result::Ok(table.chunks(chunks).children(children))
+            }
+
+            fn from_table<HffReader: ::std::fmt::Debug + #krate::read::ContentReader>(
+                table: &#krate::read::TableView<'_, HffReader>,
+            ) -> #krate::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#read,)*
+                })
+            }
+        }
+    })
+}
+
+/// Parse the table identifier and crate path from the struct attributes.
+fn struct_attrs(input: &DeriveInput) -> syn::Result<(LitStr, Path)> {
+    let mut id = None;
+    let mut krate = default_crate()?;
+    for attr in hff_attrs(&input.attrs) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("id") {
+                id = Some(meta.value()?.parse::<LitStr>()?);
+            } else if meta.path.is_ident("crate") {
+                krate = meta.value()?.parse::<LitStr>()?.parse()?;
+            } else {
+                return Err(meta.error("Expected `id` or `crate`."));
+            }
+            Ok(())
+        })?;
+    }
+    let id = id.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "Missing the table id: #[hff(id = \"...\")].")
+    })?;
+    Ok((id, krate))
+}
+
+/// The path to hff_core when not given on the struct.  Both the `hff`
+/// facade and `hff-std` re-export it, the facade is preferred when the
+/// crate being compiled depends on it under any name.
+fn default_crate() -> syn::Result<Path> {
+    let path = match crate_name("hff").or_else(|_| crate_name("hff-std")) {
+        Ok(FoundCrate::Itself) => "crate::hff_core".to_owned(),
+        Ok(FoundCrate::Name(name)) => format!("::{}::hff_core", name),
+        // Neither is a direct dependency, expect the struct to give the path.
+        Err(_) => "::hff_std::hff_core".to_owned(),
+    };
+    syn::parse_str(&path)
+}
+
+/// Parse the attributes of a field.
+fn field_attr(attrs: &[Attribute], name: &Ident) -> syn::Result<FieldAttr> {
+    let mut kind = None;
+    let mut encoding = None;
+    for attr in hff_attrs(attrs) {
+        attr.parse_nested_meta(|meta| {
+            let found = if meta.path.is_ident("id") {
+                Kind::Chunk(meta.value()?.parse()?)
+            } else if meta.path.is_ident("metadata") {
+                Kind::Metadata
+            } else if meta.path.is_ident("child") {
+                Kind::Child
+            } else if meta.path.is_ident("children") {
+                Kind::Children
+            } else if meta.path.is_ident("skip") {
+                Kind::Skip
+            } else if meta.path.is_ident("encoding") {
+                let value = meta.value()?.parse::<LitStr>()?;
+                let variant = match value.value().to_ascii_lowercase().as_str() {
+                    "bincode" => "Bincode",
+                    "cbor" => "Cbor",
+                    "json" => "Json",
+                    "msgpack" | "messagepack" => "MessagePack",
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "Expected bincode, cbor, json or msgpack.",
+                        ))
+                    }
+                };
+                encoding = Some(Ident::new(variant, value.span()));
+                return Ok(());
+            } else {
+                return Err(meta.error(
+                    "Expected `id`, `metadata`, `child`, `children`, `skip` or `encoding`.",
+                ));
+            };
+            if kind.replace(found).is_some() {
+                return Err(meta.error("The field is already mapped."));
+            }
+            Ok(())
+        })?;
+    }
+    let kind = kind.ok_or_else(|| {
+        syn::Error::new_spanned(
+            name,
+            "Map the field with #[hff(id = \"...\")], #[hff(metadata)], #[hff(child)], \
+             #[hff(children)] or #[hff(skip)].",
+        )
+    })?;
+    Ok(FieldAttr { kind, encoding })
+}
+
+/// The attributes belonging to the derive.
+fn hff_attrs(attrs: &[Attribute]) -> impl Iterator<Item = &Attribute> {
+    attrs.iter().filter(|attr| attr.path().is_ident("hff"))
+}
+
+/// Build the identifier expression from "Primary" or "Primary:Secondary".
+fn identifier(krate: &Path, id: &LitStr) -> syn::Result<TokenStream2> {
+    let value = id.value();
+    let (primary, secondary) = value.split_once(':').unwrap_or((&value, ""));
+    for part in [primary, secondary] {
+        if part.len() > 8 || part.contains(':') {
+            return Err(syn::Error::new_spanned(
+                id,
+                "Identifiers are one or two codes of at most eight bytes separated by `:`.",
+            ));
+        }
+    }
+    if primary.is_empty() {
+        return Err(syn::Error::new_spanned(
+            id,
+            "The identifier can't be empty.",
+        ));
+    }
+    let secondary = if secondary.is_empty() {
+        quote! { #krate::Ecc::INVALID }
+    } else {
+        let secondary = LitStr::new(secondary, Span::call_site());
+        quote! { #krate::Ecc::new(#secondary) }
+    };
+    let primary = LitStr::new(primary, Span::call_site());
+    Ok(quote! { (#krate::Ecc::new(#primary), #secondary) })
+}
+
+/// Error for types the derive can't handle.
+fn unsupported(input: &DeriveInput) -> syn::Error {
+    syn::Error::new_spanned(
+        &input.ident,
+        "HffTable can only be derived for structs with named fields.",
+    )
+}
@@ -0,0 +1,135 @@
+use hff::{
+    hff_std::{hff, read_validated, Writer},
+    ContentInfo, Ecc, Encoding, Error, HffTable, IdType, LE,
+};
+
+#[derive(Debug, PartialEq, HffTable)]
+#[hff(id = "Material")]
+struct Material {
+    #[hff(metadata)]
+    name: String,
+    #[hff(id = "Texture:Diffuse")]
+    diffuse: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, HffTable)]
+#[hff(id = "Lod")]
+struct Lod {
+    #[hff(id = "Indices")]
+    indices: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, HffTable)]
+#[hff(id = "Mesh")]
+struct Mesh {
+    #[hff(metadata)]
+    name: String,
+    #[hff(id = "Verts")]
+    vertices: Vec<u8>,
+    #[hff(child)]
+    material: Material,
+    #[hff(children)]
+    lods: Vec<Lod>,
+    #[hff(skip)]
+    cache: Option<u32>,
+}
+
+#[test]
+fn derive() {
+    let mesh = Mesh {
+        name: "Cube".into(),
+        vertices: (0..96).collect(),
+        material: Material {
+            name: "Stone".into(),
+            diffuse: vec![7; 64],
+        },
+        lods: vec![
+            Lod {
+                indices: (0..36).collect(),
+            },
+            Lod {
+                indices: (0..12).collect(),
+            },
+        ],
+        cache: Some(3),
+    };
+
+    let mut buffer = vec![];
+    hff([mesh.to_table().unwrap()])
+        .write::<LE>(IdType::Ecc2, "Test", &mut buffer)
+        .unwrap();
+    let file = read_validated(&mut buffer.as_slice()).unwrap();
+
+    let root = file.tables().next().unwrap();
+    assert_eq!(root.identifier(), Mesh::identifier());
+    assert_eq!(root.child_count(), 3);
+    let material = root.find_child(Material::identifier()).unwrap();
+    assert_eq!(
+        material.chunks().next().unwrap().identifier(),
+        (Ecc::new("Texture"), Ecc::new("Diffuse")).into()
+    );
+
+    let read = Mesh::from_table(&root).unwrap();
+    assert_eq!(read.cache, None);
+    assert_eq!(
+        read,
+        Mesh {
+            cache: None,
+            ..mesh
+        }
+    );
+
+    // Tables which don't match the structure are reported.
+    assert!(matches!(
+        Lod::from_table(&material),
+        Err(Error::NotFound(_))
+    ));
+    assert!(Material::from_child(&material).is_err());
+}
+
+#[test]
+fn serde() {
+    #[derive(Debug, PartialEq, HffTable)]
+    #[hff(id = "Settings")]
+    struct Settings {
+        #[hff(metadata, encoding = "json")]
+        tags: Vec<String>,
+        #[hff(id = "Scale", encoding = "cbor")]
+        scale: (f32, f32),
+    }
+
+    let settings = Settings {
+        tags: vec!["fast".into(), "small".into()],
+        scale: (0.5, 2.0),
+    };
+    let mut buffer = vec![];
+    hff([settings.to_table().unwrap()])
+        .write::<LE>(IdType::Ecc2, "Test", &mut buffer)
+        .unwrap();
+    let file = read_validated(&mut buffer.as_slice()).unwrap();
+    let root = file.tables().next().unwrap();
+    assert_eq!(root.encoding().unwrap(), Encoding::Json);
+    assert_eq!(Settings::from_table(&root).unwrap(), settings);
+}
+
+#[test]
+fn crate_path() {
+    // The path can point at any re-export of hff_core.
+    #[derive(Debug, PartialEq, hff_std::HffTable)]
+    #[hff(id = "Plain", crate = "::hff_std::hff_core")]
+    struct Plain {
+        #[hff(id = "Data")]
+        data: Vec<u8>,
+    }
+
+    let plain = Plain {
+        data: vec![1, 2, 3],
+    };
+    let mut buffer = vec![];
+    hff_std::hff([plain.to_table().unwrap()])
+        .write::<hff_std::LE>(IdType::Ecc2, "Test", &mut buffer)
+        .unwrap();
+    let file = hff_std::read_validated(&mut buffer.as_slice()).unwrap();
+    let root = file.tables().next().unwrap();
+    assert_eq!(Plain::from_table(&root).unwrap(), plain);
+}
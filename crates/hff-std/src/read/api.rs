@@ -1,16 +1,30 @@
-use crate::{ReadSeek, StdReader};
+use crate::{FileReader, MmapReader, ReadSeek, StdReader};
 use hff_core::{
-    byteorder::ReadBytesExt,
-    read::{Hff, Inspection},
-    ByteOrder, Chunk, ChunkCache, Ecc, Endian, Error, Header, Result, Table, Version, BE, LE, NE,
-    OP,
+    read::{validate, validate_footer, validate_footer_header, validate_header, Hff, Inspection},
+    ByteOrder, Chunk, ChunkCache, Error, Footer, Header, Result, Section, Table, NE, OP,
+};
+use std::{
+    fs::File,
+    io::{Cursor, Read, SeekFrom},
+    mem::size_of,
+    ops::Range,
+    path::Path,
 };
-use std::{io::Read, mem::size_of};
 
 /// Opens the input and maintains it for random access to the
 /// metadata and chunks.
-pub fn open(mut source: impl ReadSeek + 'static) -> Result<Hff<StdReader>> {
-    let (header, tables, chunks) = read_hff(&mut source)?;
+pub fn open(mut source: impl ReadSeek + Send + 'static) -> Result<Hff<StdReader>> {
+    let (header, tables, chunks) = read_hff_seek(&mut source)?;
+    Ok(Hff::new(StdReader::new(source), header, tables, chunks))
+}
+
+/// Opens the input like `open` but validates the structure first.
+/// Malformed input produces an error rather than a later panic, so
+/// this should be used for content from untrusted sources.
+pub fn open_validated(mut source: impl ReadSeek + Send + 'static) -> Result<Hff<StdReader>> {
+    let length = source.seek(SeekFrom::End(0))?;
+    source.rewind()?;
+    let ((header, tables, chunks), _) = read_hff_validated(&mut source, length)?;
     Ok(Hff::new(StdReader::new(source), header, tables, chunks))
 }
 
@@ -20,6 +34,42 @@ pub fn read(source: &mut dyn Read) -> Result<Hff<ChunkCache>> {
     Ok(Hff::new(cache, header, tables, chunks))
 }
 
+/// Reads an entire Hff into memory like `read` but validates the
+/// structure first.
+pub fn read_validated(source: &mut dyn Read) -> Result<Hff<ChunkCache>> {
+    let mut buffer = vec![];
+    source.read_to_end(&mut buffer)?;
+
+    let length = buffer.len() as u64;
+    let ((header, tables, chunks), data) =
+        read_hff_validated(&mut Cursor::new(buffer.as_slice()), length)?;
+
+    let mut content = buffer.split_off(data.start as usize);
+    content.truncate((data.end - data.start) as usize);
+    let cache = ChunkCache::new(data.start as usize, content);
+    Ok(Hff::new(cache, header, tables, chunks))
+}
+
+/// Memory maps the given file and provides zero copy access to the
+/// metadata and chunks.
+pub fn map(path: impl AsRef<Path>) -> Result<Hff<MmapReader>> {
+    let file = File::open(path)?;
+    // Safety: The mapping is read only, modifying the file while it is
+    // mapped is undefined behavior and is left to the caller to avoid.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    let (header, tables, chunks) = read_hff_seek(&mut Cursor::new(&map[..]))?;
+    Ok(Hff::new(MmapReader::new(map), header, tables, chunks))
+}
+
+/// Opens the given file for random access using positional reads.
+/// Unlike `open`, reads do not share a seek position or take a lock,
+/// so the resulting Hff can be read from many threads concurrently.
+pub fn open_file(path: impl AsRef<Path>) -> Result<Hff<FileReader>> {
+    let mut file = File::open(path)?;
+    let (header, tables, chunks) = read_hff_seek(&mut file)?;
+    Ok(Hff::new(FileReader::new(file)?, header, tables, chunks))
+}
+
 /// Read the structure of a Hff into memory.  Provides access
 /// only to the structure without any of the metadata or chunk
 /// data available.
@@ -30,26 +80,81 @@ pub fn inspect(source: &mut dyn Read) -> Result<Hff<Inspection>> {
 
 // Helpers to read hff from std::io::Read traits.
 
-pub(super) fn read_hff(reader: &mut dyn Read) -> Result<(Header, Vec<Table>, Vec<Chunk>)> {
+/// The header, tables and chunks read from a stream.
+pub(crate) type Structure = (Header, Vec<Table>, Vec<Chunk>);
+
+/// Read the structure from a stream which can't seek.  The footer
+/// layout keeps the structure at the end so the remainder of such a
+/// stream has to be read to get to it.
+pub(super) fn read_hff(reader: &mut dyn Read) -> Result<Structure> {
     // The header determines the structure endianess.
     let header = read_header(reader)?;
-    let (tables, chunks) = if header.is_native_endian() {
-        (
-            read_tables::<NE>(reader, header.table_count())?,
-            read_chunks::<NE>(reader, header.chunk_count())?,
-        )
-    } else {
-        (
-            read_tables::<OP>(reader, header.table_count())?,
-            read_chunks::<OP>(reader, header.chunk_count())?,
-        )
-    };
+    if header.has_footer() {
+        let (header, tables, chunks, _) = read_footer_stream(header, reader)?;
+        return Ok((header, tables, chunks));
+    }
 
+    let (tables, chunks) =
+        read_arrays(&header, reader, header.table_count(), header.chunk_count())?;
     Ok((header, tables, chunks))
 }
 
+/// Read the structure from a stream which can seek.  The footer is
+/// found at the end of the stream and points at the structure so the
+/// data never has to be read.
+fn read_hff_seek(reader: &mut dyn ReadSeek) -> Result<Structure> {
+    let header = read_header(reader)?;
+    if !header.has_footer() {
+        let (tables, chunks) =
+            read_arrays(&header, reader, header.table_count(), header.chunk_count())?;
+        return Ok((header, tables, chunks));
+    }
+
+    let footer = seek_footer(&header, reader)?;
+    reader.seek(SeekFrom::Start(footer.directory_offset()))?;
+    let (tables, chunks) =
+        read_arrays(&header, reader, footer.table_count(), footer.chunk_count())?;
+    Ok((footer.header(&header), tables, chunks))
+}
+
+/// Read the hff structure from a stream of known length, checking the
+/// counts before allocating and the overall structure afterwards.
+/// Also returns the range of the stream holding the data.
+pub(crate) fn read_hff_validated(
+    reader: &mut dyn ReadSeek,
+    length: u64,
+) -> Result<(Structure, Range<u64>)> {
+    let header = read_header(reader)?;
+    validate_header(&header, length)?;
+    if !header.has_footer() {
+        let (tables, chunks) =
+            read_arrays(&header, reader, header.table_count(), header.chunk_count())?;
+        validate(&header, &tables, &chunks, length)?;
+
+        let data_start =
+            (Header::SIZE + Table::SIZE * tables.len() + Chunk::SIZE * chunks.len()) as u64;
+        return Ok(((header, tables, chunks), data_start..length));
+    }
+
+    let footer = seek_footer(&header, reader)?;
+    validate_footer_header(&header, &footer, length)?;
+    reader.seek(SeekFrom::Start(footer.directory_offset()))?;
+    let (tables, chunks) =
+        read_arrays(&header, reader, footer.table_count(), footer.chunk_count())?;
+    validate_footer(&header, &footer, &tables, &chunks, length)?;
+
+    let data = Header::SIZE as u64..footer.directory_offset();
+    Ok(((footer.header(&header), tables, chunks), data))
+}
+
 fn read_hff_full(reader: &mut dyn Read) -> Result<(Header, Vec<Table>, Vec<Chunk>, ChunkCache)> {
-    let (header, tables, chunks) = read_hff(reader)?;
+    let header = read_header(reader)?;
+    if header.has_footer() {
+        return read_footer_stream(header, reader);
+    }
+
+    let (tables, chunks) =
+        read_arrays(&header, reader, header.table_count(), header.chunk_count())?;
 
     let mut buffer = vec![];
     reader.read_to_end(&mut buffer)?;
@@ -62,43 +167,105 @@ fn read_hff_full(reader: &mut dyn Read) -> Result<(Header, Vec<Table>, Vec<Chunk
     Ok((header, tables, chunks, cache))
 }
 
+/// Read the remainder of a footer layout stream following the header.
+/// The data is returned in a cache while the structure is parsed out
+/// of the end of it.
+fn read_footer_stream(
+    header: Header,
+    reader: &mut dyn Read,
+) -> Result<(Header, Vec<Table>, Vec<Chunk>, ChunkCache)> {
+    let mut buffer = vec![];
+    reader.read_to_end(&mut buffer)?;
+
+    let length = (Header::SIZE + buffer.len()) as u64;
+    let footer = seek_footer(&header, &mut Cursor::new(buffer.as_slice()))?;
+    validate_footer_header(&header, &footer, length)?;
+
+    // Offsets in the footer are from the start of the stream, the buffer
+    // starts after the header.
+    let directory = (footer.directory_offset() as usize) - Header::SIZE;
+    let (tables, chunks) = read_arrays(
+        &header,
+        &mut &buffer[directory..],
+        footer.table_count(),
+        footer.chunk_count(),
+    )?;
+    buffer.truncate(directory);
+
+    let cache = ChunkCache::new(Header::SIZE, buffer);
+    Ok((footer.header(&header), tables, chunks, cache))
+}
+
+/// Read the footer from the end of the stream.
+fn seek_footer(header: &Header, reader: &mut dyn ReadSeek) -> Result<Footer> {
+    let length = reader.seek(SeekFrom::End(0))?;
+    if length < Footer::SIZE as u64 {
+        return Err(Error::Truncated {
+            section: Section::Footer,
+        });
+    }
+    reader.seek(SeekFrom::End(-(Footer::SIZE as i64)))?;
+    if header.is_native_endian() {
+        Footer::read::<NE>(reader)
+    } else {
+        Footer::read::<OP>(reader)
+    }
+}
+
+/// Read the table and chunk arrays in the endianess of the header.
+fn read_arrays(
+    header: &Header,
+    reader: &mut dyn Read,
+    table_count: u32,
+    chunk_count: u32,
+) -> Result<(Vec<Table>, Vec<Chunk>)> {
+    if header.is_native_endian() {
+        Ok((
+            read_tables::<NE>(reader, table_count)?,
+            read_chunks::<NE>(reader, chunk_count)?,
+        ))
+    } else {
+        Ok((
+            read_tables::<OP>(reader, table_count)?,
+            read_chunks::<OP>(reader, chunk_count)?,
+        ))
+    }
+}
+
 /// Read the header from a given stream.
 fn read_header(reader: &mut dyn Read) -> Result<Header> {
-    let mut magic = [0_u8; 8];
-    reader.read_exact(&mut magic)?;
-
-    // Detect the file content endianess.  NOTE: This only describes
-    // the file structure itself, the chunk content is "not" considered
-    // part of this.  It is up to the user to deal with endianess of
-    // the chunks.
-    match Ecc::HFF_MAGIC.endian(magic.into()) {
-        Some(endian) => match endian {
-            Endian::Little => Ok(Header::with(
-                magic.into(),
-                Version::read::<LE>(reader)?,
-                reader.read_u32::<LE>()?,
-                Ecc::read::<LE>(reader)?,
-                reader.read_u32::<LE>()?,
-                reader.read_u32::<LE>()?,
-            )),
-            Endian::Big => Ok(Header::with(
-                magic.into(),
-                Version::read::<BE>(reader)?,
-                reader.read_u32::<BE>()?,
-                Ecc::read::<BE>(reader)?,
-                reader.read_u32::<BE>()?,
-                reader.read_u32::<BE>()?,
-            )),
-        },
-        None => Err(Error::Invalid("Not an HFF file.".into())),
+    let mut header = [0_u8; Header::SIZE];
+    reader
+        .read_exact(&mut header)
+        .map_err(|e| Error::from(e).within(Section::Header))?;
+
+    // Detect the file content endianess and parse the header.  NOTE: This
+    // only describes the file structure itself, the chunk content is "not"
+    // considered part of this.  It is up to the user to deal with endianess
+    // of the chunks.
+    Header::try_from(header.as_slice())
+}
+
+/// Read the given number of bytes without trusting the length for
+/// allocation.  The buffer only grows as data actually arrives.
+fn read_bytes(reader: &mut dyn Read, length: u64, section: Section) -> Result<Vec<u8>> {
+    let mut buffer = vec![];
+    reader.take(length).read_to_end(&mut buffer)?;
+    if buffer.len() as u64 == length {
+        Ok(buffer)
+    } else {
+        Err(Error::Truncated { section })
     }
 }
 
 fn read_tables<E: ByteOrder>(reader: &mut dyn Read, count: u32) -> Result<Vec<Table>> {
     if count > 0 {
-        // Create a buffer with appropriate size.
-        let mut buffer = vec![0; count as usize * size_of::<Table>()];
-        reader.read_exact(&mut buffer.as_mut_slice())?;
+        // Read the table array.
+        let buffer = read_bytes(
+            reader,
+            count as u64 * size_of::<Table>() as u64,
+            Section::Tables,
+        )?;
 
         // Read all the tables out of the buffer.
         let mut tables = vec![];
@@ -117,9 +284,12 @@ fn read_tables<E: ByteOrder>(reader: &mut dyn Read, count: u32) -> Result<Vec<Ta
 
 fn read_chunks<E: ByteOrder>(reader: &mut dyn Read, count: u32) -> Result<Vec<Chunk>> {
     if count > 0 {
-        // Create a buffer with the appropriate size.
-        let mut buffer = vec![0; count as usize * size_of::<Chunk>()];
-        reader.read_exact(&mut buffer.as_mut_slice())?;
+        // Read the chunk array.
+        let buffer = read_bytes(
+            reader,
+            count as u64 * size_of::<Chunk>() as u64,
+            Section::Chunks,
+        )?;
 
         // Read the chunks out of the buffer.
         let mut chunks = vec![];
@@ -135,3 +305,230 @@ fn read_chunks<E: ByteOrder>(reader: &mut dyn Read, count: u32) -> Result<Vec<Ch
         Ok(vec![])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{checks, test_table, written, TempPath},
+        *,
+    };
+
+    #[test]
+    fn validated() {
+        use hff_core::{Chunk, Header, Table};
+
+        let buffer = written(test_table().unwrap());
+        checks(&crate::read::read_validated(&mut buffer.as_slice()).unwrap());
+        crate::read::open_validated(std::io::Cursor::new(buffer.clone())).unwrap();
+
+        let invalid = |buffer: Vec<u8>| {
+            assert!(matches!(
+                crate::read::read_validated(&mut buffer.as_slice()),
+                Err(Error::InvalidStructure(_))
+            ));
+            assert!(matches!(
+                crate::read::open_validated(std::io::Cursor::new(buffer)),
+                Err(Error::InvalidStructure(_))
+            ));
+        };
+        let table = |index: usize| Header::SIZE + index * Table::SIZE;
+        let chunk = |index: usize| table(6) + index * Chunk::SIZE;
+
+        // Truncated content.
+        invalid(buffer[..buffer.len() - 32].to_vec());
+
+        // Table counts which can't fit in the file.
+        let mut bad = buffer.clone();
+        bad[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            crate::read::read_validated(&mut bad.as_slice()),
+            Err(Error::Truncated {
+                section: Section::Tables
+            })
+        ));
+
+        // Unknown identifier type.
+        let mut bad = buffer.clone();
+        bad[12..16].copy_from_slice(&99_u32.to_le_bytes());
+        assert!(matches!(
+            crate::read::read_validated(&mut bad.as_slice()),
+            Err(Error::UnknownIdType(99))
+        ));
+
+        // A broken sibling link.
+        let mut bad = buffer.clone();
+        bad[table(1) + 36..table(1) + 40].copy_from_slice(&7_u32.to_le_bytes());
+        invalid(bad);
+
+        // More children than tables.
+        let mut bad = buffer.clone();
+        bad[table(4) + 32..table(4) + 36].copy_from_slice(&3_u32.to_le_bytes());
+        invalid(bad);
+
+        // Chunks out of range.
+        let mut bad = buffer.clone();
+        bad[table(0) + 44..table(0) + 48].copy_from_slice(&100_u32.to_le_bytes());
+        assert!(matches!(
+            crate::read::open_validated(std::io::Cursor::new(bad)),
+            Err(Error::OutOfBounds { .. })
+        ));
+
+        // Misaligned chunk content.
+        let mut bad = buffer.clone();
+        let offset = u64::from_le_bytes(bad[chunk(0) + 24..chunk(0) + 32].try_into().unwrap());
+        bad[chunk(0) + 24..chunk(0) + 32].copy_from_slice(&(offset + 1).to_le_bytes());
+        invalid(bad);
+
+        // Chunk content pointing into the table array.
+        let mut bad = buffer.clone();
+        bad[chunk(0) + 24..chunk(0) + 32].copy_from_slice(&(table(1) as u64).to_le_bytes());
+        invalid(bad);
+    }
+
+    #[test]
+    fn errors() {
+        use hff_core::{Header, Table};
+
+        let buffer = written(test_table().unwrap());
+
+        // Every reader reports the same error for the same problem.
+        let all = |buffer: &[u8], check: &dyn Fn(Error) -> bool| {
+            assert!(check(crate::read::read(&mut &buffer[..]).unwrap_err()));
+            assert!(check(crate::read::inspect(&mut &buffer[..]).unwrap_err()));
+            assert!(check(
+                crate::read::read_validated(&mut &buffer[..]).unwrap_err()
+            ));
+            assert!(check(
+                crate::read::open(std::io::Cursor::new(buffer.to_vec())).unwrap_err()
+            ));
+            assert!(check(
+                crate::read::open_validated(std::io::Cursor::new(buffer.to_vec())).unwrap_err()
+            ));
+        };
+
+        let mut bad = buffer.clone();
+        bad[0] ^= 0xff;
+        all(&bad, &|e| matches!(e, Error::BadMagic));
+
+        let mut bad = buffer.clone();
+        bad[8..10].copy_from_slice(&1_u16.to_le_bytes());
+        all(&bad, &|e| {
+            matches!(e, Error::UnsupportedVersion { found, supported }
+                if found == Version::new(1, supported.minor()) && supported != found)
+        });
+
+        let mut bad = buffer.clone();
+        bad[12..16].copy_from_slice(&99_u32.to_le_bytes());
+        all(&bad, &|e| matches!(e, Error::UnknownIdType(99)));
+
+        let truncated =
+            |section| move |e| matches!(e, Error::Truncated { section: s } if s == section);
+        all(&buffer[..Header::SIZE - 1], &truncated(Section::Header));
+        all(
+            &buffer[..Header::SIZE + Table::SIZE],
+            &truncated(Section::Tables),
+        );
+        all(
+            &buffer[..Header::SIZE + Table::SIZE * 6 + 1],
+            &truncated(Section::Chunks),
+        );
+
+        // Content past the end of the stream is reported the same way by
+        // every reader.
+        fn past_end<T: std::fmt::Debug + hff_core::read::ContentReader>(hff: &Hff<T>) {
+            let results = hff
+                .depth_first()
+                .flat_map(|(_, table)| table.chunks().collect::<Vec<_>>())
+                .map(|chunk| hff.get_decompressed(&chunk))
+                .collect::<Vec<_>>();
+            assert!(results.iter().any(|r| r.is_err()));
+            assert!(results.into_iter().all(|r| match r {
+                Ok(_) => true,
+                Err(e) => matches!(
+                    e,
+                    Error::Truncated {
+                        section: Section::Content
+                    }
+                ),
+            }));
+        }
+        let short = &buffer[..buffer.len() - 32];
+        past_end(&crate::read::open(std::io::Cursor::new(short.to_vec())).unwrap());
+        past_end(&crate::read::read(&mut &short[..]).unwrap());
+        let path = TempPath::new("errors.hff");
+        std::fs::write(&path, short).unwrap();
+        past_end(&crate::read::map(&path).unwrap());
+        past_end(&crate::read::open_file(&path).unwrap());
+
+        // Utilities report the same way.
+        let bytes = utilities::StringVec::from(["value"].iter())
+            .to_bytes::<hff_core::LE>()
+            .unwrap();
+        assert!(matches!(
+            utilities::StringVec::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(Error::Truncated {
+                section: Section::Content
+            })
+        ));
+        assert!(matches!(
+            utilities::Hierarchical::from_bytes(&bytes),
+            Err(Error::UnexpectedId { .. })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn decompressed_reader() {
+        use std::io::Read;
+
+        let text = "Streamed through the decoder a piece at a time.".repeat(64);
+        let content = hff([table((Ecc::new("Stream"), Ecc::INVALID))
+            .metadata("Not compressed.")
+            .unwrap()
+            .chunks([
+                chunk((Ecc::new("Plain"), Ecc::INVALID), text.as_str()).unwrap(),
+                chunk((Ecc::new("Packed"), Ecc::INVALID), (9, text.as_str())).unwrap(),
+            ])]);
+        let path = TempPath::new("decompressed_reader.hff");
+        {
+            let mut file = std::fs::File::create(&path).unwrap();
+            content
+                .write::<hff_core::NE>(IdType::Ecc2, "Test", &mut file)
+                .unwrap();
+        }
+
+        fn check<T: std::fmt::Debug + hff_core::read::ContentReader>(hff: &Hff<T>, text: &str) {
+            let root = hff.tables().next().unwrap();
+            let mut metadata = String::new();
+            hff.decompressed_reader(&root)
+                .unwrap()
+                .read_to_string(&mut metadata)
+                .unwrap();
+            assert_eq!(metadata, "Not compressed.");
+            for chunk in root.chunks() {
+                // Small reads exercise the streaming path rather than one big read.
+                let mut reader = hff.decompressed_reader(&chunk).unwrap();
+                let mut result = vec![];
+                let mut buffer = [0; 7];
+                loop {
+                    match reader.read(&mut buffer).unwrap() {
+                        0 => break,
+                        count => result.extend_from_slice(&buffer[..count]),
+                    }
+                }
+                assert_eq!(result, text.as_bytes());
+            }
+        }
+
+        check(
+            &crate::read::open(std::fs::File::open(&path).unwrap()).unwrap(),
+            &text,
+        );
+        check(&crate::read::open_file(&path).unwrap(), &text);
+        check(&crate::read::map(&path).unwrap(), &text);
+        check(
+            &crate::read::read(&mut std::fs::File::open(&path).unwrap()).unwrap(),
+            &text,
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use hff_core::{read::ContentReader, ContentInfo, Error, Result, Section};
+use std::{borrow::Cow, fs::File, io::Read};
+
+/// Implements a reader over a file using positional IO.  Each read
+/// supplies its own offset so there is no shared seek position and no
+/// lock, allowing any number of threads to read chunks concurrently.
+#[derive(Debug)]
+pub struct FileReader {
+    file: File,
+    length: u64,
+}
+
+impl FileReader {
+    /// Create a new positional file reader.
+    pub fn new(file: File) -> Result<Self> {
+        let length = file.metadata()?.len();
+        Ok(Self { file, length })
+    }
+
+    /// Get the content of the given item.
+    pub fn get(&self, content: &dyn ContentInfo) -> Result<Vec<u8>> {
+        match content.offset().checked_add(content.len()) {
+            Some(end) if end <= self.length => (),
+            _ => {
+                return Err(Error::Truncated {
+                    section: Section::Content,
+                })
+            }
+        }
+        let mut result = vec![0; content.len() as usize];
+        self.read_exact(content, &mut result)?;
+        Ok(result)
+    }
+
+    /// Read the content into the provided slice.
+    pub fn read_exact(&self, content: &dyn ContentInfo, buffer: &mut [u8]) -> Result<()> {
+        read_exact_at(&self.file, buffer, content.offset())
+            .map_err(|e| Error::from(e).within(Section::Content))
+    }
+}
+
+impl ContentReader for FileReader {
+    fn content(&self, content: &dyn ContentInfo) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Owned(self.get(content)?))
+    }
+
+    fn stream(&self, content: &dyn ContentInfo) -> Result<Box<dyn Read + '_>> {
+        Ok(Box::new(Positional {
+            file: &self.file,
+            offset: content.offset(),
+            remaining: content.len(),
+        }))
+    }
+}
+
+/// Streams a range of the file with positional reads.
+struct Positional<'a> {
+    file: &'a File,
+    offset: u64,
+    remaining: u64,
+}
+
+impl<'a> Read for Positional<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let count = (buf.len() as u64).min(self.remaining) as usize;
+        if count == 0 {
+            return Ok(0);
+        }
+        let count = read_at(self.file, &mut buf[..count], self.offset)?;
+        self.offset += count as u64;
+        self.remaining -= count as u64;
+        Ok(count)
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    use std::os::unix::fs::FileExt;
+    file.read_at(buffer, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    use std::os::windows::fs::FileExt;
+    file.seek_read(buffer, offset)
+}
+
+#[cfg(unix)]
+fn read_exact_at(file: &File, buffer: &mut [u8], offset: u64) -> std::io::Result<()> {
+    use std::os::unix::fs::FileExt;
+    file.read_exact_at(buffer, offset)
+}
+
+#[cfg(windows)]
+fn read_exact_at(file: &File, mut buffer: &mut [u8], mut offset: u64) -> std::io::Result<()> {
+    use std::os::windows::fs::FileExt;
+    while !buffer.is_empty() {
+        match file.seek_read(buffer, offset) {
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(count) => {
+                buffer = &mut buffer[count..];
+                offset += count as u64;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{test_table, TempPath},
+        *,
+    };
+
+    #[test]
+    fn open_file() {
+        fn shareable<T: Send + Sync>(_: &T) {}
+
+        // Write the test table to a file for positional access.
+        let path = TempPath::new("open_file.hff");
+        {
+            let content = test_table().unwrap();
+            let mut file = std::fs::File::create(&path).unwrap();
+            content
+                .lazy_write::<hff_core::NE>(IdType::Ecc2, "Test", &mut file)
+                .unwrap();
+        }
+
+        let hff = crate::read::open_file(&path).unwrap();
+        let cached = read(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        shareable(&hff);
+
+        // Read every table and chunk from separate threads at once.
+        std::thread::scope(|scope| {
+            for ((_, table), (_, cached_table)) in hff.depth_first().zip(cached.depth_first()) {
+                let (hff, cached) = (&hff, &cached);
+                scope.spawn(move || {
+                    assert_eq!(table.identifier(), cached_table.identifier());
+                    if table.has_metadata() {
+                        assert_eq!(
+                            hff.get(&table).unwrap(),
+                            cached.read(&cached_table).unwrap()
+                        );
+                    }
+                    for (chunk, cached_chunk) in table.chunks().zip(cached_table.chunks()) {
+                        let mut buffer = vec![0; chunk.len() as usize];
+                        hff.read_exact(&chunk, &mut buffer).unwrap();
+                        assert_eq!(buffer, cached.read(&cached_chunk).unwrap());
+                    }
+                });
+            }
+        });
+    }
+}
@@ -0,0 +1,99 @@
+use hff_core::{read::ContentReader, ContentInfo, Error, Result, Section};
+use memmap2::Mmap;
+use std::borrow::Cow;
+
+/// Implements a memory mapped reader over a file.  All access is
+/// through slices directly into the mapping, so there is no locking
+/// and any number of threads can read concurrently.
+pub struct MmapReader {
+    map: Mmap,
+}
+
+impl std::fmt::Debug for MmapReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MmapReader({})", self.map.len())
+    }
+}
+
+impl MmapReader {
+    /// Create a new memory mapped reader from the given mapping.
+    pub fn new(map: Mmap) -> Self {
+        Self { map }
+    }
+
+    /// Get the entire mapped content.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Get a slice representing the given content.
+    pub fn read(&self, content: &dyn ContentInfo) -> Result<&'_ [u8]> {
+        let start = content.offset();
+        match start.checked_add(content.len()) {
+            Some(end) if end <= self.map.len() as u64 => {
+                Ok(&self.map[start as usize..end as usize])
+            }
+            _ => Err(Error::Truncated {
+                section: Section::Content,
+            }),
+        }
+    }
+}
+
+impl ContentReader for MmapReader {
+    fn content(&self, content: &dyn ContentInfo) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Borrowed(self.read(content)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        test_support::{checks, test_table, TempPath},
+        *,
+    };
+
+    #[test]
+    fn map() {
+        // Write the test table to a file so it can be mapped.
+        let path = TempPath::new("map.hff");
+        {
+            let content = test_table().unwrap();
+            let mut file = std::fs::File::create(&path).unwrap();
+            content
+                .lazy_write::<hff_core::NE>(IdType::Ecc2, "Test", &mut file)
+                .unwrap();
+        }
+
+        // Compare the mapped content against the fully read content.
+        let mapped = crate::read::map(&path).unwrap();
+        let cached = read(&mut std::fs::File::open(&path).unwrap()).unwrap();
+        checks(&cached);
+
+        // The mapped hff can be shared across threads without locking.
+        std::thread::scope(|scope| {
+            for ((_, mapped_table), (_, cached_table)) in
+                mapped.depth_first().zip(cached.depth_first())
+            {
+                let (mapped, cached) = (&mapped, &cached);
+                scope.spawn(move || {
+                    assert_eq!(mapped_table.identifier(), cached_table.identifier());
+                    if mapped_table.has_metadata() {
+                        assert_eq!(
+                            mapped.read(&mapped_table).unwrap(),
+                            cached.read(&cached_table).unwrap()
+                        );
+                    }
+                    for (mapped_chunk, cached_chunk) in
+                        mapped_table.chunks().zip(cached_table.chunks())
+                    {
+                        assert_eq!(
+                            mapped.read(&mapped_chunk).unwrap(),
+                            cached.read(&cached_chunk).unwrap()
+                        );
+                    }
+                });
+            }
+        });
+    }
+}
@@ -1,5 +1,11 @@
 mod std_reader;
 pub use std_reader::StdReader;
 
+mod mmap_reader;
+pub use mmap_reader::MmapReader;
+
+mod file_reader;
+pub use file_reader::FileReader;
+
 mod api;
 pub use api::*;
@@ -1,9 +1,12 @@
 use crate::ReadSeek;
-use hff_core::{ContentInfo, Error, Result};
+use hff_core::{read::ContentReader, ContentInfo, Error, Result, Section};
+use std::{borrow::Cow, io::Read, sync::PoisonError};
 
 /// Implements a std reader wrapper around the source.
 pub struct StdReader {
-    source: std::sync::Mutex<Box<dyn ReadSeek>>,
+    /// The source.  Every access seeks before reading, so the source is
+    /// still usable after a panic while it was locked.
+    source: std::sync::Mutex<Box<dyn ReadSeek + Send>>,
 }
 
 impl std::fmt::Debug for StdReader {
@@ -14,7 +17,7 @@ impl std::fmt::Debug for StdReader {
 
 impl StdReader {
     /// Create a new std reader type.
-    pub fn new(source: impl ReadSeek + 'static) -> Self {
+    pub fn new(source: impl ReadSeek + Send + 'static) -> Self {
         Self {
             source: std::sync::Mutex::new(Box::new(source)),
         }
@@ -22,39 +25,126 @@ impl StdReader {
 
     /// Get the content of the given item.
     pub fn get(&self, content: &dyn ContentInfo) -> Result<Vec<u8>> {
-        let mut source = self
-            .source
-            .lock()
-            .map_err(|e| Error::Invalid(e.to_string()))?;
+        let mut source = self.source.lock().unwrap_or_else(PoisonError::into_inner);
         source.seek(std::io::SeekFrom::Start(content.offset()))?;
 
-        let mut result = vec![0; content.len() as usize];
-        source.read_exact(&mut result)?;
-        Ok(result)
+        // Let the buffer grow as the data is read rather than trusting
+        // the length for allocation.
+        let mut result = vec![];
+        (&mut **source)
+            .take(content.len())
+            .read_to_end(&mut result)?;
+        if result.len() as u64 == content.len() {
+            Ok(result)
+        } else {
+            Err(Error::Truncated {
+                section: Section::Content,
+            })
+        }
     }
 
     /// Read the content into the provided slice.
     pub fn read_exact(&self, content: &dyn ContentInfo, buffer: &mut [u8]) -> Result<()> {
-        let mut source = self
-            .source
-            .lock()
-            .map_err(|e| Error::Invalid(e.to_string()))?;
+        let mut source = self.source.lock().unwrap_or_else(PoisonError::into_inner);
         source.seek(std::io::SeekFrom::Start(content.offset()))?;
 
-        source.read_exact(buffer)?;
-        Ok(())
+        source
+            .read_exact(buffer)
+            .map_err(|e| Error::from(e).within(Section::Content))
     }
 
     /// Get the slice of data representing the content requested.
     pub fn read(
         &self,
         content: &dyn ContentInfo,
-    ) -> Result<std::sync::MutexGuard<'_, Box<dyn ReadSeek>>> {
+    ) -> Result<std::sync::MutexGuard<'_, Box<dyn ReadSeek + Send>>> {
+        let mut source = self.source.lock().unwrap_or_else(PoisonError::into_inner);
+        source.seek(std::io::SeekFrom::Start(content.offset()))?;
+        Ok(source)
+    }
+}
+
+impl ContentReader for StdReader {
+    fn content(&self, content: &dyn ContentInfo) -> Result<Cow<'_, [u8]>> {
+        Ok(Cow::Owned(self.get(content)?))
+    }
+
+    fn stream(&self, content: &dyn ContentInfo) -> Result<Box<dyn Read + '_>> {
+        Ok(Box::new(Positioned {
+            reader: self,
+            position: content.offset(),
+            remaining: content.len(),
+        }))
+    }
+}
+
+/// Streams content from the source.  Each read locks and seeks on its
+/// own so other access to the source can happen while a stream is open.
+struct Positioned<'a> {
+    /// The reader holding the source.
+    reader: &'a StdReader,
+    /// Position of the next read in the source.
+    position: u64,
+    /// Bytes of content left to read.
+    remaining: u64,
+}
+
+impl<'a> Read for Positioned<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let length = (buf.len() as u64).min(self.remaining) as usize;
+        if length == 0 {
+            return Ok(0);
+        }
+
         let mut source = self
+            .reader
             .source
             .lock()
-            .map_err(|e| Error::Invalid(e.to_string()))?;
-        source.seek(std::io::SeekFrom::Start(content.offset()))?;
-        Ok(source)
+            .unwrap_or_else(PoisonError::into_inner);
+        source.seek(std::io::SeekFrom::Start(self.position))?;
+        let count = source.read(&mut buf[..length])?;
+        self.position += count as u64;
+        self.remaining -= count as u64;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{test_table, written};
+    use std::io::Read;
+
+    #[test]
+    fn interleaved() {
+        let buffer = written(test_table().unwrap());
+        let hff = crate::read::open(std::io::Cursor::new(buffer)).unwrap();
+        let root = hff.tables().next().unwrap();
+        let chunks = root.chunks().collect::<Vec<_>>();
+        let expected = chunks
+            .iter()
+            .map(|chunk| hff.get(chunk).unwrap())
+            .collect::<Vec<_>>();
+
+        // Two streams and direct reads of other content take turns.
+        let mut first = hff.decompressed_reader(&chunks[0]).unwrap();
+        let mut second = hff.decompressed_reader(&chunks[1]).unwrap();
+        let (mut read_first, mut read_second) = (vec![], vec![]);
+        let mut buffer = [0; 5];
+        loop {
+            let count = first.read(&mut buffer).unwrap();
+            read_first.extend_from_slice(&buffer[..count]);
+            assert_eq!(hff.get(&chunks[2]).unwrap(), expected[2]);
+            let other = second.read(&mut buffer).unwrap();
+            read_second.extend_from_slice(&buffer[..other]);
+            assert_eq!(
+                hff.get(&root).unwrap(),
+                "This is some metadata attached to the table.".as_bytes()
+            );
+            if count == 0 && other == 0 {
+                break;
+            }
+        }
+        assert_eq!(read_first, expected[0]);
+        assert_eq!(read_second, expected[1]);
     }
 }
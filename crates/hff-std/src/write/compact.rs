@@ -0,0 +1,106 @@
+use super::{ChecksumWriter, HffEditor};
+use crate::ReadSeek;
+use hff_core::{Checksum, Result, NE, OP};
+use std::io::{SeekFrom, Write};
+
+/// The sizes before and after compacting an hff.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Compaction {
+    /// Size of the source.
+    pub original: u64,
+    /// Size of the compacted output.
+    pub compacted: u64,
+}
+
+impl Compaction {
+    /// The number of bytes reclaimed by compacting.
+    pub fn reclaimed(&self) -> u64 {
+        self.original.saturating_sub(self.compacted)
+    }
+}
+
+/// Rewrite an hff keeping only the content referenced by table metadata
+/// and chunks.  Tables keep their order, content is laid out in tree
+/// order and entries referring to the same range share one copy.  The
+/// endianess of the source is preserved.
+pub fn compact(mut source: impl ReadSeek, writer: &mut dyn Write) -> Result<Compaction> {
+    let original = source.seek(SeekFrom::End(0))?;
+    let editor = HffEditor::open(source)?;
+
+    let mut writer = ChecksumWriter {
+        writer,
+        hasher: Checksum::None.hasher(),
+        length: 0,
+    };
+    if editor.is_native_endian() {
+        editor.write::<NE>(&mut writer)?;
+    } else {
+        editor.write::<OP>(&mut writer)?;
+    }
+
+    Ok(Compaction {
+        original,
+        compacted: writer.length,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support::test_table, *};
+
+    #[test]
+    fn compact() {
+        use hff_core::{Chunk, Header, Table};
+
+        let mut original = vec![];
+        test_table()
+            .unwrap()
+            .write::<hff_core::BE>(IdType::Ecc2, "Test", &mut original)
+            .unwrap();
+
+        // Already compact files come out unchanged.
+        let mut output = vec![];
+        let compaction = crate::compact(std::io::Cursor::new(&original), &mut output).unwrap();
+        assert_eq!(compaction.reclaimed(), 0);
+        assert_eq!(output, original);
+
+        // Point the second chunk at the content of the first by hand,
+        // leaving its own content unreferenced, and add trailing junk.
+        let mut patched = original.clone();
+        let chunk = |index: usize| Header::SIZE + 6 * Table::SIZE + index * Chunk::SIZE;
+        patched.copy_within(chunk(0) + 16..chunk(0) + 56, chunk(1) + 16);
+        patched.extend_from_slice(&[0xff; 100]);
+
+        let mut output = vec![];
+        let compaction = crate::compact(std::io::Cursor::new(&patched), &mut output).unwrap();
+        let unreferenced = "Chunks provided to the table will maintain their order."
+            .len()
+            .next_multiple_of(16) as u64;
+        assert_eq!(compaction.original, patched.len() as u64);
+        assert_eq!(compaction.compacted, output.len() as u64);
+        assert_eq!(compaction.reclaimed(), unreferenced + 100);
+
+        // Everything still reads back the same, the shared content once.
+        let before = crate::read::read(&mut patched.as_slice()).unwrap();
+        let after = crate::read::read_validated(&mut output.as_slice()).unwrap();
+        assert!(!after.is_native_endian());
+        let chunks = after.tables().next().unwrap().chunks().collect::<Vec<_>>();
+        assert_eq!(chunks[0].offset(), chunks[1].offset());
+        for ((_, before_table), (_, after_table)) in before.depth_first().zip(after.depth_first()) {
+            assert_eq!(before_table.identifier(), after_table.identifier());
+            assert_eq!(before_table.has_metadata(), after_table.has_metadata());
+            if before_table.has_metadata() {
+                assert_eq!(
+                    before.get_decompressed(&before_table).unwrap(),
+                    after.get_decompressed(&after_table).unwrap()
+                );
+            }
+            for (b, a) in before_table.chunks().zip(after_table.chunks()) {
+                assert_eq!(
+                    before.get_decompressed(&b).unwrap(),
+                    after.get_decompressed(&a).unwrap()
+                );
+            }
+        }
+    }
+}
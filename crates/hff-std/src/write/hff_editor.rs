@@ -0,0 +1,706 @@
+use super::write_data;
+use crate::{read::read_hff_validated, ReadSeek};
+use hff_core::{
+    write::{ChunkDesc, DataArray, DataSource},
+    ByteOrder, Checksum, Chunk, Ecc, Encoding, Error, Header, IdType, Identifier, Result, Section,
+    Table,
+};
+use std::{
+    collections::HashMap,
+    io::{Read, SeekFrom, Write},
+};
+
+/// Edits an existing hff.  The structure is read into an editable
+/// tree where tables, chunks and metadata can be added, replaced and
+/// removed.  Writing produces a new hff where everything left untouched
+/// is copied directly from the source without being decompressed or
+/// otherwise held in memory.
+pub struct HffEditor<'a, R: ReadSeek> {
+    /// The hff being edited.
+    source: R,
+    /// The header of the source.
+    header: Header,
+    /// The checksum to compute for new metadata and chunks.
+    checksum: Checksum,
+    /// The root level tables.
+    tables: Vec<TableEdit<'a>>,
+}
+
+impl<'a, R: ReadSeek> HffEditor<'a, R> {
+    /// Open the given hff for editing.  The structure is validated since
+    /// the content will be copied from it.
+    pub fn open(mut source: R) -> Result<Self> {
+        let length = source.seek(SeekFrom::End(0))?;
+        source.rewind()?;
+        let ((header, tables, chunks), _) = read_hff_validated(&mut source, length)?;
+
+        let mut roots = vec![];
+        let mut index = 0;
+        while index < tables.len() {
+            let (table, next) = TableEdit::read(&tables, &chunks, index);
+            roots.push(table);
+            index = next;
+        }
+
+        Ok(Self {
+            source,
+            header,
+            checksum: Checksum::None,
+            tables: roots,
+        })
+    }
+
+    /// Compute and store the given checksum for new metadata and chunks.
+    /// Content copied from the source keeps its recorded checksum.
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Identifier type of the source.
+    pub fn id_type(&self) -> IdType {
+        self.header.id_type()
+    }
+
+    /// The content type of the source.
+    pub fn content_type(&self) -> Ecc {
+        self.header.content_type()
+    }
+
+    /// Check if the source is in native endian.
+    pub fn is_native_endian(&self) -> bool {
+        self.header.is_native_endian()
+    }
+
+    /// The root level tables.
+    pub fn tables(&self) -> &[TableEdit<'a>] {
+        &self.tables
+    }
+
+    /// Get a table by the path of child indices leading to it, starting
+    /// with the index at the root level.
+    pub fn table_mut(&mut self, path: &[usize]) -> Option<&mut TableEdit<'a>> {
+        let (first, rest) = path.split_first()?;
+        let mut table = self.tables.get_mut(*first)?;
+        for index in rest {
+            table = table.children.get_mut(*index)?;
+        }
+        Some(table)
+    }
+
+    /// Add a new table at the end of the root level.
+    pub fn add_table(&mut self, identifier: impl Into<Identifier>) -> &mut TableEdit<'a> {
+        self.tables.push(TableEdit::new(identifier));
+        self.tables.last_mut().unwrap()
+    }
+
+    /// Remove the table at the given path along with all its children.
+    pub fn remove_table(&mut self, path: &[usize]) -> Result<TableEdit<'a>> {
+        let missing = || Error::Invalid(format!("No table at {:?}.", path));
+        let (last, parent) = path.split_last().ok_or_else(missing)?;
+        let tables = if parent.is_empty() {
+            &mut self.tables
+        } else {
+            &mut self.table_mut(parent).ok_or_else(missing)?.children
+        };
+        if *last < tables.len() {
+            Ok(tables.remove(*last))
+        } else {
+            Err(missing())
+        }
+    }
+
+    /// Write the edited hff to the given stream.  New content is
+    /// prepared up front so the structure can be written first, copied
+    /// content streams straight from the source.  Returns the source.
+    pub fn write<E: ByteOrder>(mut self, writer: &mut dyn Write) -> Result<R> {
+        // Flatten the tree into the table and chunk arrays along with the
+        // content in the order it is written.
+        let mut tables = vec![];
+        let mut chunks = vec![];
+        let mut contents = vec![];
+        TableEdit::flatten_level(self.tables, &mut tables, &mut chunks, &mut contents);
+
+        // Lay out the content after the arrays keeping the alignment of
+        // each entry.  Entries referring to the same source range keep
+        // sharing a single copy when it satisfies their alignment.
+        let data_start =
+            (Header::SIZE + tables.len() * Table::SIZE + chunks.len() * Chunk::SIZE) as u64;
+        let mut offset = data_start;
+        let mut copies = HashMap::new();
+        let mut locations = vec![];
+        for (target, content) in &mut contents {
+            let stored = content.prepare(self.checksum)?;
+            let location = match content {
+                Content::Stored(_) => copies
+                    .get(&(stored.offset, stored.length))
+                    .copied()
+                    .filter(|location| location % stored.alignment as u64 == 0),
+                Content::New { .. } => None,
+            };
+            let is_shared = location.is_some();
+            let location =
+                location.unwrap_or_else(|| DataArray::align(0, offset, stored.alignment));
+            locations.push((!is_shared).then_some(location));
+            match *target {
+                Target::Metadata(index) => {
+                    let table = &mut tables[index];
+                    *table.metadata_offset_mut() = location;
+                    *table.metadata_length_mut() = stored.length;
+                    *table.metadata_checksum_mut() = stored.checksum;
+                    *table.checksum_type_mut() = stored.checksum_type;
+                    *table.metadata_codec_mut() = stored.codec;
+                    *table.metadata_uncompressed_length_mut() = stored.uncompressed_length;
+                    *table.metadata_alignment_mut() = stored.alignment;
+                    *table.metadata_encoding_mut() = stored.encoding;
+                }
+                Target::Chunk(index) => {
+                    let chunk = &mut chunks[index];
+                    *chunk.offset_mut() = location;
+                    *chunk.length_mut() = stored.length;
+                    *chunk.checksum_mut() = stored.checksum;
+                    *chunk.checksum_type_mut() = stored.checksum_type;
+                    *chunk.codec_mut() = stored.codec;
+                    *chunk.uncompressed_length_mut() = stored.uncompressed_length;
+                    *chunk.alignment_mut() = stored.alignment;
+                    *chunk.encoding_mut() = stored.encoding;
+                }
+            }
+            if !is_shared {
+                if let Content::Stored(_) = content {
+                    copies
+                        .entry((stored.offset, stored.length))
+                        .or_insert(location);
+                }
+                offset = location + stored.length;
+            }
+        }
+
+        // Write the structure.
+        let header = Header::new(
+            self.header.id_type(),
+            self.header.content_type(),
+            tables.len() as u32,
+            chunks.len() as u32,
+        );
+        writer.write_all(&header.to_bytes::<E>()?)?;
+        for table in tables {
+            table.write::<E>(writer)?;
+        }
+        for chunk in chunks {
+            chunk.write::<E>(writer)?;
+        }
+
+        // And the content.
+        let mut position = data_start;
+        for ((_, content), location) in contents.into_iter().zip(locations) {
+            let Some(location) = location else {
+                continue;
+            };
+            writer.write_all(&vec![0; (location - position) as usize])?;
+            let length = match content {
+                Content::Stored(stored) => {
+                    self.source.seek(SeekFrom::Start(stored.offset))?;
+                    let copied =
+                        std::io::copy(&mut (&mut self.source).take(stored.length), writer)?;
+                    if copied != stored.length {
+                        return Err(Error::Truncated {
+                            section: Section::Content,
+                        });
+                    }
+                    copied
+                }
+                Content::New { source, .. } => write_data(source, Checksum::None, writer)?.0,
+            };
+            position = location + length;
+        }
+
+        // The data always ends on a 16 byte boundary.
+        let padding = DataArray::align(0, position, Chunk::ALIGNMENT) - position;
+        writer.write_all(&vec![0; padding as usize])?;
+        writer.flush()?;
+
+        Ok(self.source)
+    }
+}
+
+/// A table being edited.
+pub struct TableEdit<'a> {
+    /// The table identifier.
+    identifier: Identifier,
+    /// The metadata if any.
+    metadata: Option<Content<'a>>,
+    /// The chunks in order.
+    chunks: Vec<(Identifier, Content<'a>)>,
+    /// The child tables in order.
+    children: Vec<TableEdit<'a>>,
+}
+
+impl<'a> TableEdit<'a> {
+    /// Create a new empty table.
+    pub fn new(identifier: impl Into<Identifier>) -> Self {
+        Self {
+            identifier: identifier.into(),
+            metadata: None,
+            chunks: vec![],
+            children: vec![],
+        }
+    }
+
+    /// Get the identifier.
+    pub fn identifier(&self) -> Identifier {
+        self.identifier
+    }
+
+    /// Change the identifier.
+    pub fn set_identifier(&mut self, identifier: impl Into<Identifier>) {
+        self.identifier = identifier.into();
+    }
+
+    /// Check if the table has metadata.
+    pub fn has_metadata(&self) -> bool {
+        self.metadata.is_some()
+    }
+
+    /// Set or replace the metadata.  Replaced metadata keeps its
+    /// encoding and alignment.
+    pub fn set_metadata<T>(&mut self, content: T) -> Result<()>
+    where
+        T: TryInto<DataSource<'a>, Error = Error>,
+    {
+        let content = Content::new(content.try_into()?);
+        self.metadata = Some(match self.metadata.take() {
+            Some(replaced) => content.layout_of(&replaced),
+            None => content,
+        });
+        Ok(())
+    }
+
+    /// Record the serde encoding of the metadata.
+    pub fn set_metadata_encoding(&mut self, encoding: Encoding) -> Result<()> {
+        let metadata = self
+            .metadata
+            .as_mut()
+            .ok_or_else(|| Error::Invalid("The table has no metadata.".into()))?;
+        *metadata.encoding_mut() = encoding.into();
+        Ok(())
+    }
+
+    /// Align the metadata to the given number of bytes within the file.
+    /// The alignment is rounded up to a power of two of at least 16 and
+    /// can't be larger than `Chunk::MAX_ALIGNMENT`.
+    pub fn set_metadata_alignment(&mut self, alignment: u32) -> Result<()> {
+        let metadata = self
+            .metadata
+            .as_mut()
+            .ok_or_else(|| Error::Invalid("The table has no metadata.".into()))?;
+        *metadata.alignment_mut() = Chunk::round_alignment(alignment)?;
+        Ok(())
+    }
+
+    /// Remove the metadata.
+    pub fn remove_metadata(&mut self) {
+        self.metadata = None;
+    }
+
+    /// The identifiers of the chunks in order.
+    pub fn chunks(&self) -> impl Iterator<Item = Identifier> + '_ {
+        self.chunks.iter().map(|(identifier, _)| *identifier)
+    }
+
+    /// Append a chunk.
+    pub fn add_chunk<T>(&mut self, identifier: impl Into<Identifier>, content: T) -> Result<()>
+    where
+        T: TryInto<DataSource<'a>, Error = Error>,
+    {
+        self.chunks
+            .push((identifier.into(), Content::new(content.try_into()?)));
+        Ok(())
+    }
+
+    /// Append a chunk keeping the encoding and alignment of the
+    /// description.
+    pub fn add_chunk_desc(&mut self, chunk: ChunkDesc<'a>) {
+        let identifier = chunk.identifier();
+        let encoding = chunk.encoding().into();
+        let alignment = chunk.alignment().unwrap_or(Chunk::ALIGNMENT);
+        self.chunks.push((
+            identifier,
+            Content::New {
+                source: chunk.data_source(),
+                alignment,
+                encoding,
+            },
+        ));
+    }
+
+    /// Replace the content of the chunk at the given index, keeping its
+    /// identifier, encoding and alignment.
+    pub fn replace_chunk<T>(&mut self, index: usize, content: T) -> Result<()>
+    where
+        T: TryInto<DataSource<'a>, Error = Error>,
+    {
+        let chunk = self.chunk_mut(index)?;
+        *chunk = Content::new(content.try_into()?).layout_of(chunk);
+        Ok(())
+    }
+
+    /// Record the serde encoding of the chunk at the given index.
+    pub fn set_chunk_encoding(&mut self, index: usize, encoding: Encoding) -> Result<()> {
+        *self.chunk_mut(index)?.encoding_mut() = encoding.into();
+        Ok(())
+    }
+
+    /// Align the chunk at the given index to the given number of bytes
+    /// within the file.  The alignment is rounded up to a power of two of
+    /// at least 16 and can't be larger than `Chunk::MAX_ALIGNMENT`.
+    pub fn set_chunk_alignment(&mut self, index: usize, alignment: u32) -> Result<()> {
+        let alignment = Chunk::round_alignment(alignment)?;
+        *self.chunk_mut(index)?.alignment_mut() = alignment;
+        Ok(())
+    }
+
+    /// Get the content of the chunk at the given index.
+    fn chunk_mut(&mut self, index: usize) -> Result<&mut Content<'a>> {
+        self.chunks
+            .get_mut(index)
+            .map(|(_, content)| content)
+            .ok_or_else(|| Error::Invalid(format!("No chunk at {}.", index)))
+    }
+
+    /// Remove the chunk at the given index.
+    pub fn remove_chunk(&mut self, index: usize) -> Result<()> {
+        if index < self.chunks.len() {
+            self.chunks.remove(index);
+            Ok(())
+        } else {
+            Err(Error::Invalid(format!("No chunk at {}.", index)))
+        }
+    }
+
+    /// The child tables.
+    pub fn children(&self) -> &[TableEdit<'a>] {
+        &self.children
+    }
+
+    /// Get a child table.
+    pub fn child_mut(&mut self, index: usize) -> Option<&mut TableEdit<'a>> {
+        self.children.get_mut(index)
+    }
+
+    /// Add a new child table after the existing children.
+    pub fn add_child(&mut self, identifier: impl Into<Identifier>) -> &mut TableEdit<'a> {
+        self.children.push(TableEdit::new(identifier));
+        self.children.last_mut().unwrap()
+    }
+
+    /// Remove the child at the given index along with all its children.
+    pub fn remove_child(&mut self, index: usize) -> Result<TableEdit<'a>> {
+        if index < self.children.len() {
+            Ok(self.children.remove(index))
+        } else {
+            Err(Error::Invalid(format!("No child table at {}.", index)))
+        }
+    }
+
+    /// Build the table at the given index and its children from the
+    /// source structure.  Returns the index following the subtree.
+    fn read(tables: &[Table], chunks: &[Chunk], index: usize) -> (Self, usize) {
+        let table = &tables[index];
+        let metadata = (table.metadata_length() > 0).then(|| {
+            Content::Stored(Stored {
+                offset: table.metadata_offset(),
+                length: table.metadata_length(),
+                checksum: table.metadata_checksum(),
+                checksum_type: table.checksum_type(),
+                codec: table.metadata_codec(),
+                uncompressed_length: table.metadata_uncompressed_length(),
+                alignment: table.metadata_alignment(),
+                encoding: table.metadata_encoding(),
+            })
+        });
+        let first = table.chunk_index() as usize;
+        let table_chunks = chunks[first..first + table.chunk_count() as usize]
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.identifier(),
+                    Content::Stored(Stored {
+                        offset: chunk.offset(),
+                        length: chunk.length(),
+                        checksum: chunk.checksum(),
+                        checksum_type: chunk.checksum_type(),
+                        codec: chunk.codec(),
+                        uncompressed_length: chunk.uncompressed_length(),
+                        alignment: chunk.alignment(),
+                        encoding: chunk.encoding(),
+                    }),
+                )
+            })
+            .collect();
+
+        let mut children = vec![];
+        let mut next = index + 1;
+        for _ in 0..table.child_count() {
+            let (child, after) = Self::read(tables, chunks, next);
+            children.push(child);
+            next = after;
+        }
+
+        (
+            Self {
+                identifier: table.identifier(),
+                metadata,
+                chunks: table_chunks,
+                children,
+            },
+            next,
+        )
+    }
+
+    /// Push the tables of a level and their children onto the arrays in
+    /// depth first order, linking the siblings.
+    fn flatten_level(
+        level: Vec<Self>,
+        tables: &mut Vec<Table>,
+        chunks: &mut Vec<Chunk>,
+        contents: &mut Vec<(Target, Content<'a>)>,
+    ) {
+        let mut previous: Option<usize> = None;
+        for table in level {
+            let index = tables.len();
+            if let Some(previous) = previous.replace(index) {
+                *tables[previous].sibling_mut() = (index - previous) as u32;
+            }
+            table.flatten(tables, chunks, contents);
+        }
+    }
+
+    /// Push the table and its children onto the arrays in depth first
+    /// order.
+    fn flatten(
+        self,
+        tables: &mut Vec<Table>,
+        chunks: &mut Vec<Chunk>,
+        contents: &mut Vec<(Target, Content<'a>)>,
+    ) {
+        let index = tables.len();
+        let mut table = Table::create().identifier(self.identifier).end();
+        *table.child_count_mut() = self.children.len() as u32;
+        if !self.chunks.is_empty() {
+            *table.chunk_index_mut() = chunks.len() as u32;
+            *table.chunk_count_mut() = self.chunks.len() as u32;
+        }
+        tables.push(table);
+
+        if let Some(metadata) = self.metadata {
+            contents.push((Target::Metadata(index), metadata));
+        }
+        for (identifier, content) in self.chunks {
+            contents.push((Target::Chunk(chunks.len()), content));
+            chunks.push(Chunk::new(identifier, 0, 0));
+        }
+
+        Self::flatten_level(self.children, tables, chunks, contents);
+    }
+}
+
+/// Where content ends up in the flattened structure.
+enum Target {
+    /// The metadata of the table at the index.
+    Metadata(usize),
+    /// The chunk at the index.
+    Chunk(usize),
+}
+
+/// Content of a table or chunk.
+enum Content<'a> {
+    /// Content left untouched in the source.
+    Stored(Stored),
+    /// Content added while editing.
+    New {
+        /// The source of the content.
+        source: DataSource<'a>,
+        /// Alignment of the content offset.
+        alignment: u32,
+        /// The serde encoding of the content.
+        encoding: u32,
+    },
+}
+
+impl<'a> Content<'a> {
+    /// New content with no encoding and the default alignment.
+    fn new(source: DataSource<'a>) -> Self {
+        Self::New {
+            source,
+            alignment: Chunk::ALIGNMENT,
+            encoding: Encoding::None.into(),
+        }
+    }
+
+    /// Take the encoding and alignment of the content being replaced.
+    fn layout_of(mut self, replaced: &Self) -> Self {
+        let (alignment, encoding) = match replaced {
+            Self::Stored(stored) => (stored.alignment, stored.encoding),
+            Self::New {
+                alignment,
+                encoding,
+                ..
+            } => (*alignment, *encoding),
+        };
+        *self.alignment_mut() = alignment;
+        *self.encoding_mut() = encoding;
+        self
+    }
+
+    /// Mutable access to the alignment.
+    fn alignment_mut(&mut self) -> &mut u32 {
+        match self {
+            Self::Stored(stored) => &mut stored.alignment,
+            Self::New { alignment, .. } => alignment,
+        }
+    }
+
+    /// Mutable access to the serde encoding.
+    fn encoding_mut(&mut self) -> &mut u32 {
+        match self {
+            Self::Stored(stored) => &mut stored.encoding,
+            Self::New { encoding, .. } => encoding,
+        }
+    }
+
+    /// Prepare the content for writing and describe how it will be stored.
+    fn prepare(&mut self, checksum: Checksum) -> Result<Stored> {
+        match self {
+            Self::Stored(stored) => Ok(*stored),
+            Self::New {
+                source,
+                alignment,
+                encoding,
+            } => {
+                let length = source.prepare()?;
+                Ok(Stored {
+                    offset: 0,
+                    length,
+                    checksum: source.checksum(checksum)?,
+                    checksum_type: checksum.into(),
+                    codec: source.codec().into(),
+                    uncompressed_length: source.uncompressed_len(),
+                    alignment: *alignment,
+                    encoding: *encoding,
+                })
+            }
+        }
+    }
+}
+
+/// Location and description of content within the source.
+#[derive(Copy, Clone)]
+struct Stored {
+    /// Offset in the source.
+    offset: u64,
+    /// Length of the stored content.
+    length: u64,
+    /// The recorded checksum.
+    checksum: u64,
+    /// The checksum type.
+    checksum_type: u32,
+    /// The codec the content is compressed with.
+    codec: u32,
+    /// Length of the content once decompressed.
+    uncompressed_length: u64,
+    /// Alignment of the content offset.
+    alignment: u32,
+    /// The serde encoding of the content.
+    encoding: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_support::test_table, *};
+
+    #[test]
+    fn editor() {
+        let mut original = vec![];
+        test_table()
+            .unwrap()
+            .with_checksum(Checksum::XxHash64)
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut original)
+            .unwrap();
+
+        // Without edits everything is copied as is.
+        let mut unedited = vec![];
+        HffEditor::open(std::io::Cursor::new(original.as_slice()))
+            .unwrap()
+            .write::<hff_core::LE>(&mut unedited)
+            .unwrap();
+        assert_eq!(unedited, original);
+
+        let mut editor = HffEditor::open(std::io::Cursor::new(original.as_slice()))
+            .unwrap()
+            .with_checksum(Checksum::Crc32c);
+        let root = editor.table_mut(&[0]).unwrap();
+        root.replace_chunk(1, "A replaced chunk.").unwrap();
+        root.remove_chunk(2).unwrap();
+        assert!(root.remove_chunk(10).is_err());
+        root.add_chunk((Ecc::new("TRC6"), Ecc::new("TRS6")), "An appended chunk.")
+            .unwrap();
+        let c1 = editor.table_mut(&[0, 0, 0]).unwrap();
+        c1.set_metadata("Metadata added to a table.").unwrap();
+        editor.table_mut(&[0, 1]).unwrap().remove_metadata();
+        editor.remove_table(&[0, 0, 1]).unwrap();
+        assert!(editor.remove_table(&[0, 0, 1]).is_err());
+        let added = editor.add_table((Ecc::new("Added"), Ecc::new("Sub")));
+        added.set_metadata("A new root.").unwrap();
+        added
+            .add_child((Ecc::new("Child"), Ecc::new("Sub")))
+            .add_chunk((Ecc::new("C0"), Ecc::new("S0")), "A new child chunk.")
+            .unwrap();
+
+        let mut edited = vec![];
+        editor.write::<hff_core::BE>(&mut edited).unwrap();
+        let hff = crate::read::read_validated(&mut edited.as_slice()).unwrap();
+        assert!(hff.verify().is_empty());
+
+        let ids = hff
+            .depth_first()
+            .map(|(depth, table)| (depth, table.identifier().as_ecc2().0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            vec![
+                (0, Ecc::new("Test")),
+                (1, Ecc::new("C0Prime")),
+                (2, Ecc::new("C1Prime")),
+                (1, Ecc::new("C4Prime")),
+                (0, Ecc::new("Added")),
+                (1, Ecc::new("Child")),
+            ]
+        );
+
+        let tables = hff.depth_first().map(|(_, t)| t).collect::<Vec<_>>();
+        let root = &tables[0];
+        let chunks = root
+            .chunks()
+            .map(|c| {
+                (
+                    c.identifier().as_ecc2().0,
+                    hff.get_decompressed(&c).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 6);
+        assert_eq!(chunks[1], (Ecc::new("TRC1"), b"A replaced chunk.".to_vec()));
+        assert_eq!(chunks[2].0, Ecc::new("TRC3"));
+        assert_eq!(chunks[4].1, b"In the case of a lazy_write, the file will be opened and streamed directly to the writer without being buffered in memory.");
+        assert_eq!(
+            chunks[5],
+            (Ecc::new("TRC6"), b"An appended chunk.".to_vec())
+        );
+        assert_eq!(hff.read(&tables[2]).unwrap(), b"Metadata added to a table.");
+        assert!(!tables[3].has_metadata());
+        assert_eq!(hff.read(&tables[4]).unwrap(), b"A new root.");
+        let child = tables[5].chunks().next().unwrap();
+        assert_eq!(hff.read(&child).unwrap(), b"A new child chunk.");
+    }
+}
@@ -0,0 +1,317 @@
+use super::write_data;
+use hff_core::{
+    write::DataSource, ByteOrder, Checksum, Chunk, Ecc, Error, Footer, Header, IdType, Identifier,
+    Result, Table,
+};
+use std::io::Write;
+
+/// Writes hff content incrementally.  Tables are opened, given metadata
+/// and chunks and then closed one at a time, the content is written as
+/// it arrives so only the structure is held in memory.  The footer
+/// layout is used so the table and chunk arrays simply follow the data
+/// at finalization, the stream is only ever appended to.
+pub struct HffWriter<W: Write> {
+    /// The output stream.
+    writer: W,
+    /// Writes the arrays and footer in the endian of the header.
+    write_structure: fn(&[Entry], Footer, &mut dyn Write) -> Result<()>,
+    /// Length of the data written so far, including padding.
+    data_length: u64,
+    /// The checksum to compute for metadata and chunks.
+    checksum: Checksum,
+    /// The tables in depth first order with their chunks.
+    tables: Vec<Entry>,
+    /// Indices of the currently open tables, innermost last.
+    open: Vec<usize>,
+    /// The most recent child at each level, the root level first.
+    last_child: Vec<Option<usize>>,
+}
+
+/// A table being written along with its chunks.
+struct Entry {
+    /// The table.
+    table: Table,
+    /// The chunks of the table.
+    chunks: Vec<Chunk>,
+    /// Whether metadata has been given, it may be empty.
+    has_metadata: bool,
+}
+
+impl<W: Write> HffWriter<W> {
+    /// Start writing a new hff at the current position of the stream.
+    /// The header is written immediately in the given endian.
+    pub fn new<E: ByteOrder>(
+        mut writer: W,
+        id_type: IdType,
+        content_type: impl Into<Ecc>,
+    ) -> Result<Self> {
+        let header = Header::footer(id_type, content_type.into());
+        writer.write_all(&header.to_bytes::<E>()?)?;
+        Ok(Self {
+            writer,
+            write_structure: write_structure::<E>,
+            data_length: 0,
+            checksum: Checksum::None,
+            tables: vec![],
+            open: vec![],
+            last_child: vec![None],
+        })
+    }
+
+    /// Compute and store the given checksum for all metadata and chunks.
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Open a new table.  It is a child of the currently open table or
+    /// at the root level if no table is open.
+    pub fn open_table(&mut self, identifier: impl Into<Identifier>) -> Result<()> {
+        let index = self.tables.len();
+
+        // The previous table at this level gets its sibling now that we
+        // know its subtree is complete.
+        if let Some(previous) = self
+            .last_child
+            .last_mut()
+            .and_then(|last| last.replace(index))
+        {
+            *self.tables[previous].table.sibling_mut() = (index - previous) as u32;
+        }
+        if let Some(&parent) = self.open.last() {
+            *self.tables[parent].table.child_count_mut() += 1;
+        }
+
+        self.tables.push(Entry {
+            table: Table::create().identifier(identifier.into()).end(),
+            chunks: vec![],
+            has_metadata: false,
+        });
+        self.open.push(index);
+        self.last_child.push(None);
+        Ok(())
+    }
+
+    /// Write the metadata of the currently open table.
+    pub fn metadata<'a, T>(&mut self, content: T) -> Result<()>
+    where
+        T: TryInto<DataSource<'a>, Error = Error>,
+    {
+        let index = self.current()?;
+        if self.tables[index].has_metadata {
+            return Err(Error::DuplicateMetadata(format!(
+                "Table {} already has metadata.",
+                index
+            )));
+        }
+
+        let source = content.try_into()?;
+        let (codec, uncompressed) = (source.codec(), source.uncompressed_len());
+        let (offset, length, checksum) = self.write_content(source)?;
+
+        self.tables[index].has_metadata = true;
+        let table = &mut self.tables[index].table;
+        *table.metadata_offset_mut() = offset;
+        *table.metadata_length_mut() = length;
+        *table.metadata_checksum_mut() = checksum;
+        *table.checksum_type_mut() = self.checksum.into();
+        *table.metadata_codec_mut() = codec.into();
+        *table.metadata_uncompressed_length_mut() = uncompressed;
+        Ok(())
+    }
+
+    /// Write a chunk into the currently open table.
+    pub fn chunk<'a, T>(&mut self, identifier: impl Into<Identifier>, content: T) -> Result<()>
+    where
+        T: TryInto<DataSource<'a>, Error = Error>,
+    {
+        let index = self.current()?;
+
+        let source = content.try_into()?;
+        let (codec, uncompressed) = (source.codec(), source.uncompressed_len());
+        let (offset, length, checksum) = self.write_content(source)?;
+
+        let mut chunk = Chunk::new(identifier, length, offset);
+        *chunk.checksum_mut() = checksum;
+        *chunk.checksum_type_mut() = self.checksum.into();
+        *chunk.codec_mut() = codec.into();
+        *chunk.uncompressed_length_mut() = uncompressed;
+        self.tables[index].chunks.push(chunk);
+        Ok(())
+    }
+
+    /// Close the currently open table.
+    pub fn close_table(&mut self) -> Result<()> {
+        self.current()?;
+        self.open.pop();
+        self.last_child.pop();
+        Ok(())
+    }
+
+    /// Finish writing.  The table and chunk arrays are written after the
+    /// data followed by the footer pointing at them.  Returns the stream
+    /// positioned at the end of the hff.
+    pub fn finish(mut self) -> Result<W> {
+        if !self.open.is_empty() {
+            return Err(Error::Invalid(format!(
+                "{} tables are still open.",
+                self.open.len()
+            )));
+        }
+
+        let mut chunk_index = 0;
+        for entry in &mut self.tables {
+            if !entry.chunks.is_empty() {
+                *entry.table.chunk_index_mut() = chunk_index;
+                *entry.table.chunk_count_mut() = entry.chunks.len() as u32;
+                chunk_index += entry.chunks.len() as u32;
+            }
+        }
+
+        let footer = Footer::new(
+            Header::SIZE as u64 + self.data_length,
+            self.tables.len() as u32,
+            chunk_index,
+        );
+        (self.write_structure)(&self.tables, footer, &mut self.writer)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Get the index of the currently open table.
+    fn current(&self) -> Result<usize> {
+        self.open
+            .last()
+            .copied()
+            .ok_or_else(|| Error::Invalid("No table is open.".into()))
+    }
+
+    /// Write the content at the end of the data.  Returns the offset,
+    /// the length and the checksum.
+    fn write_content(&mut self, source: DataSource) -> Result<(u64, u64, u64)> {
+        let offset = Header::SIZE as u64 + self.data_length;
+        let (length, checksum) = write_data(source, self.checksum, &mut self.writer)?;
+
+        let padding = length.next_multiple_of(16) - length;
+        self.writer.write_all(&vec![0; padding as usize])?;
+        self.data_length += length + padding;
+
+        Ok((offset, length, checksum))
+    }
+}
+
+/// Write the table and chunk arrays followed by the footer.
+fn write_structure<E: ByteOrder>(
+    tables: &[Entry],
+    footer: Footer,
+    writer: &mut dyn Write,
+) -> Result<()> {
+    for entry in tables {
+        entry.table.write::<E>(writer)?;
+    }
+    for chunk in tables.iter().flat_map(|entry| &entry.chunks) {
+        chunk.write::<E>(writer)?;
+    }
+    footer.write::<E>(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn hff_writer() {
+        // Build the same content with the tree builder and incrementally.
+        let content = hff([
+            table((Ecc::new("Root"), Ecc::INVALID))
+                .metadata("Root metadata.")
+                .unwrap()
+                .chunks([
+                    chunk((Ecc::new("C0"), Ecc::INVALID), "First chunk.").unwrap(),
+                    chunk((Ecc::new("C1"), Ecc::INVALID), "Second chunk.").unwrap(),
+                ])
+                .children([
+                    table((Ecc::new("Child0"), Ecc::INVALID))
+                        .chunks([chunk((Ecc::new("C2"), Ecc::INVALID), "Nested.").unwrap()])
+                        .children([table((Ecc::new("Leaf"), Ecc::INVALID))
+                            .metadata("Leaf metadata.")
+                            .unwrap()]),
+                    table((Ecc::new("Child1"), Ecc::INVALID)),
+                    table((Ecc::new("Child2"), Ecc::INVALID)).chunks([chunk(
+                        (Ecc::new("C3"), Ecc::INVALID),
+                        "Last.",
+                    )
+                    .unwrap()]),
+                ]),
+            table((Ecc::new("Second"), Ecc::INVALID))
+                .metadata("Another root.")
+                .unwrap(),
+        ])
+        .with_checksum(Checksum::Crc32c);
+        let mut expected = vec![];
+        content
+            .stream_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut expected)
+            .unwrap();
+
+        // Start part way into a stream to make sure offsets are relative,
+        // the stream is only appended to.
+        let mut writer = HffWriter::new::<hff_core::LE>(vec![0xff; 5], IdType::Ecc2, "Test")
+            .unwrap()
+            .with_checksum(Checksum::Crc32c);
+        writer.open_table((Ecc::new("Root"), Ecc::INVALID)).unwrap();
+        writer.metadata("Root metadata.").unwrap();
+        writer
+            .chunk((Ecc::new("C0"), Ecc::INVALID), "First chunk.")
+            .unwrap();
+        writer
+            .chunk((Ecc::new("C1"), Ecc::INVALID), "Second chunk.")
+            .unwrap();
+        writer
+            .open_table((Ecc::new("Child0"), Ecc::INVALID))
+            .unwrap();
+        writer
+            .chunk((Ecc::new("C2"), Ecc::INVALID), "Nested.")
+            .unwrap();
+        writer.open_table((Ecc::new("Leaf"), Ecc::INVALID)).unwrap();
+        writer.metadata("Leaf metadata.").unwrap();
+        assert!(writer.metadata("Again.").is_err());
+        writer.close_table().unwrap();
+        writer.close_table().unwrap();
+        writer
+            .open_table((Ecc::new("Child1"), Ecc::INVALID))
+            .unwrap();
+        writer.close_table().unwrap();
+        writer
+            .open_table((Ecc::new("Child2"), Ecc::INVALID))
+            .unwrap();
+        writer
+            .chunk((Ecc::new("C3"), Ecc::INVALID), "Last.")
+            .unwrap();
+        writer.close_table().unwrap();
+        writer.close_table().unwrap();
+        writer
+            .open_table((Ecc::new("Second"), Ecc::INVALID))
+            .unwrap();
+        writer.metadata("Another root.").unwrap();
+        writer.close_table().unwrap();
+        assert!(writer.close_table().is_err());
+        let stream = writer.finish().unwrap();
+        assert_eq!(&stream[5..], expected.as_slice());
+
+        // Tables left open can not be finished.
+        let mut writer = HffWriter::new::<hff_core::LE>(vec![], IdType::Ecc2, "Test").unwrap();
+        writer.open_table((Ecc::new("Open"), Ecc::INVALID)).unwrap();
+        assert!(writer.finish().is_err());
+
+        // Metadata can only be given once, even when empty.
+        let mut writer = HffWriter::new::<hff_core::LE>(vec![], IdType::Ecc2, "Test").unwrap();
+        writer
+            .open_table((Ecc::new("Empty"), Ecc::INVALID))
+            .unwrap();
+        writer.metadata("").unwrap();
+        assert!(matches!(
+            writer.metadata(""),
+            Err(Error::DuplicateMetadata(_))
+        ));
+    }
+}
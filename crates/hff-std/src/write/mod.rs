@@ -1,11 +1,25 @@
 use crate::WriteSeek;
 use hff_core::{
+    read::DedupStats,
     write::{DataArray, DataSource, HffDesc},
-    ByteOrder, Ecc, Header, IdType, Result,
+    ByteOrder, Checksum, ChecksumHasher, Chunk, Ecc, Footer, Header, IdType, Result,
 };
-use std::io::Write;
+use std::{
+    io::{Read, Write},
+    path::Path,
+};
+
+mod hff_writer;
+pub use hff_writer::HffWriter;
+
+mod hff_editor;
+pub use hff_editor::{HffEditor, TableEdit};
+
+mod compact;
+pub use compact::{compact, Compaction};
 
-/// Writer trait for HffDesc.
+/// Writer trait for HffDesc.  Each write returns how much content the
+/// written structure shares between entries.
 pub trait Writer {
     /// Write to a stream.
     fn write<E: ByteOrder>(
@@ -13,16 +27,29 @@ pub trait Writer {
         id_type: IdType,
         content_type: impl Into<Ecc>,
         writer: &mut dyn Write,
-    ) -> Result<()>;
+    ) -> Result<DedupStats>;
 
     /// Write to a stream but finalize chunk lazilly during the write.
     /// This requires a stream with both Write and Seek capabilities.
+    /// Compressed content is compressed directly into the stream so
+    /// it is never buffered in memory.
     fn lazy_write<E: ByteOrder>(
         self,
         id_type: IdType,
         content_type: impl Into<Ecc>,
         writer: &mut dyn WriteSeek,
-    ) -> Result<()>;
+    ) -> Result<DedupStats>;
+
+    /// Write to a stream in a single pass using the footer layout.  The
+    /// data is written straight after the header and the table and chunk
+    /// arrays follow it, so nothing is buffered and the stream does not
+    /// need to Seek.  Suitable for pipes, sockets and stdout.
+    fn stream_write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc>,
+        writer: &mut dyn Write,
+    ) -> Result<DedupStats>;
 }
 
 impl<'a> Writer for HffDesc<'a> {
@@ -31,10 +58,29 @@ impl<'a> Writer for HffDesc<'a> {
         id_type: IdType,
         content_type: impl Into<Ecc>,
         writer: &mut dyn Write,
-    ) -> Result<()> {
+    ) -> Result<DedupStats> {
         let offset_to_blob = self.offset_to_blob() as u64;
+        let checksum = self.checksum();
+        let parallel = self.parallel();
+        let dedup = self.dedup();
         let (mut tables, mut chunks, mut data) = self.finish();
 
+        // Store identical content once and prepare all the data in the
+        // data array so we have offsets, length and checksums.
+        let map = data.compress_and_dedup(parallel, dedup)?;
+        let offset_len = data.prepare_with(checksum, parallel)?;
+
+        // Update the table metadata length/offset/checksum and chunk length/offset/checksum.
+        let stats = HffDesc::update_data(
+            &mut tables,
+            &mut chunks,
+            offset_to_blob,
+            checksum,
+            &DataArray::expand(&map, &offset_len),
+        );
+
+        // Nothing is written until the content has been laid out, so
+        // content which can't be fails without partial output.
         let header = Header::new(
             id_type,
             content_type.into(),
@@ -43,19 +89,14 @@ impl<'a> Writer for HffDesc<'a> {
         );
         writer.write_all(header.to_bytes::<E>()?.as_slice())?;
 
-        // Prepare all the data in the data array so we have offsets and length.
-        let offset_len = data.prepare()?;
-
-        // Update the table metadata length/offset and chunk length/offset.
-        HffDesc::update_data(&mut tables, &mut chunks, offset_to_blob, &offset_len);
-
         // And write the content+data blob.
         writer.write_all(tables.to_bytes::<E>()?.as_slice())?;
         writer.write_all(chunks.to_bytes::<E>()?.as_slice())?;
-        let _test = write_data_array(data, writer)?;
-        assert_eq!(_test, offset_len);
+        // Files are read again while writing so they are checked against
+        // what was prepared.
+        write_data_array(data, checksum, Some(&offset_len), writer)?;
 
-        Ok(())
+        Ok(stats)
     }
 
     fn lazy_write<E: ByteOrder>(
@@ -63,10 +104,13 @@ impl<'a> Writer for HffDesc<'a> {
         id_type: IdType,
         content_type: impl Into<Ecc>,
         mut writer: &mut dyn WriteSeek,
-    ) -> Result<()> {
+    ) -> Result<DedupStats> {
         let array_size = self.arrays_size();
         let offset_to_blob = self.offset_to_blob() as u64;
-        let (mut tables, mut chunks, data) = self.finish();
+        let checksum = self.checksum();
+        let parallel = self.parallel();
+        let dedup = self.dedup();
+        let (mut tables, mut chunks, mut data) = self.finish();
 
         let header = Header::new(
             id_type,
@@ -81,11 +125,22 @@ impl<'a> Writer for HffDesc<'a> {
         // differences between different backing types.
         writer.write_all(&mut vec![0; array_size])?;
 
-        // Write the data and record the offset/length information.
-        let offset_len = write_data_array(data, &mut writer)?;
+        // Compress everything up front when running in parallel and store
+        // identical content once, the checksums are computed as the data
+        // is written.
+        let map = data.compress_and_dedup(parallel, dedup)?;
 
-        // Update the table metadata length/offset and chunk length/offset.
-        HffDesc::update_data(&mut tables, &mut chunks, offset_to_blob, &offset_len);
+        // Write the data and record the offset/length/checksum information.
+        let offset_len = write_data_array(data, checksum, None, &mut writer)?;
+
+        // Update the table metadata length/offset/checksum and chunk length/offset/checksum.
+        let stats = HffDesc::update_data(
+            &mut tables,
+            &mut chunks,
+            offset_to_blob,
+            checksum,
+            &DataArray::expand(&map, &offset_len),
+        );
 
         // Seek back to the tables/chunks.
         writer.seek(std::io::SeekFrom::Start(Header::SIZE as u64))?;
@@ -94,57 +149,209 @@ impl<'a> Writer for HffDesc<'a> {
         writer.write_all(tables.to_bytes::<E>()?.as_slice())?;
         writer.write_all(chunks.to_bytes::<E>()?.as_slice())?;
 
-        Ok(())
+        Ok(stats)
+    }
+
+    fn stream_write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc>,
+        writer: &mut dyn Write,
+    ) -> Result<DedupStats> {
+        let checksum = self.checksum();
+        let parallel = self.parallel();
+        let dedup = self.dedup();
+        let (mut tables, mut chunks, mut data) = self.finish();
+        // The data immediately follows the header in the footer layout.
+        data.set_base(Header::SIZE as u64);
+
+        let header = Header::footer(id_type, content_type.into());
+        writer.write_all(header.to_bytes::<E>()?.as_slice())?;
+
+        // Compress everything up front when running in parallel and store
+        // identical content once, the checksums are computed as the data
+        // is written.
+        let map = data.compress_and_dedup(parallel, dedup)?;
+
+        // The data immediately follows the header.
+        let offset_len = write_data_array(data, checksum, None, writer)?;
+        let data_length = offset_len
+            .last()
+            .map(|(offset, length, _)| offset + length.next_multiple_of(16))
+            .unwrap_or(0);
+
+        // Update the table metadata length/offset/checksum and chunk length/offset/checksum.
+        let stats = HffDesc::update_data(
+            &mut tables,
+            &mut chunks,
+            Header::SIZE as u64,
+            checksum,
+            &DataArray::expand(&map, &offset_len),
+        );
+
+        // Then the tables and chunks followed by the footer pointing at them.
+        let footer = Footer::new(
+            Header::SIZE as u64 + data_length,
+            tables.len() as u32,
+            chunks.len() as u32,
+        );
+        writer.write_all(tables.to_bytes::<E>()?.as_slice())?;
+        writer.write_all(chunks.to_bytes::<E>()?.as_slice())?;
+        footer.write::<E>(writer)?;
+        writer.flush()?;
+
+        Ok(stats)
     }
 }
 
 /// Write the data to the given stream.
-/// Returns a vector of offset into the writer (starting from 0)
-/// and the length of the data written without alignment padding.
-fn write_data_array(data_array: DataArray, writer: &mut dyn Write) -> Result<Vec<(u64, u64)>> {
+/// Returns a vector of offset into the writer (starting from 0),
+/// the length of the data written without alignment padding and
+/// the checksum of the data.  Fails if any entry differs from the
+/// expected layout when given.
+fn write_data_array(
+    data_array: DataArray,
+    checksum: Checksum,
+    expected: Option<&[(u64, u64, u64)]>,
+    writer: &mut dyn Write,
+) -> Result<Vec<(u64, u64, u64)>> {
     let mut offset_len = vec![];
+    let base = data_array.base();
+    let alignments = data_array.alignments();
 
     // Track where we are in the writer, starting from zero.
     let mut offset = 0;
-    for mut item in data_array {
-        // Prepare each item.
-        // This is only for compressed data (at this time) to perform
-        // the compression.  Using std write here means it all has to
-        // be buffered into memory.
-        item.prepare()?;
-
-        // Write in the appropriate manner.
-        let length = match item {
-            DataSource::File(mut f, _) => std::io::copy(&mut f, writer)?,
-            DataSource::Owned(data) => std::io::copy(&mut data.as_slice(), writer)?,
-            DataSource::Ref(mut data) => std::io::copy(&mut data, writer)?,
-            #[cfg(feature = "compression")]
-            DataSource::Compressed(_, _, data) => {
-                std::io::copy(&mut data.unwrap().as_slice(), writer)?
+    for (item, alignment) in data_array.into_iter().zip(alignments) {
+        // Pad up to the alignment of the entry.
+        let aligned = DataArray::align(base, offset, alignment);
+        writer.write_all(&vec![0; (aligned - offset) as usize])?;
+        offset = aligned;
+
+        let path = item.file_path().map(Path::to_path_buf);
+        let (length, item_checksum) = write_data(item, checksum, writer)?;
+
+        // Record the offset, length and checksum.
+        let entry = (offset, length, item_checksum);
+        if expected.is_some_and(|expected| expected.get(offset_len.len()) != Some(&entry)) {
+            return Err(DataSource::content_changed(path.as_deref()));
+        }
+        offset_len.push(entry);
+        offset += length;
+    }
+
+    // The data always ends on a 16 byte boundary.
+    let padding = DataArray::align(base, offset, Chunk::ALIGNMENT) - offset;
+    writer.write_all(&vec![0; padding as usize])?;
+
+    Ok(offset_len)
+}
+
+/// Write a single data source to the given stream without alignment
+/// padding.  Returns the length written and the checksum of the data.
+fn write_data(item: DataSource, checksum: Checksum, writer: &mut dyn Write) -> Result<(u64, u64)> {
+    // Write in the appropriate manner, computing the checksum and
+    // length as the data passes through.  Compressed content which
+    // was not prepared up front is compressed straight into the
+    // writer so it never has to be held in memory.
+    let mut writer = ChecksumWriter {
+        writer: &mut *writer,
+        hasher: checksum.hasher(),
+        length: 0,
+    };
+    match item {
+        DataSource::File(f, length) => {
+            if std::io::copy(&mut f.take(length), &mut writer)? != length {
+                return Err(DataSource::content_changed(None));
             }
-        };
+        }
+        DataSource::Owned(data) => writer.write_all(&data)?,
+        DataSource::Ref(data) => writer.write_all(data)?,
+        DataSource::Path(path, length) => {
+            if std::io::copy(&mut open_path(&path, length)?, &mut writer)? != length {
+                return Err(DataSource::path_changed(&path));
+            }
+        }
+        DataSource::Range(range) => {
+            std::io::copy(&mut range.stream()?, &mut writer)?;
+        }
+        DataSource::Reader(mut stream) => {
+            std::io::copy(&mut stream, &mut writer)?;
+        }
+        #[cfg(feature = "compression")]
+        DataSource::Compressed(_, _, _, _, Some(data)) => writer.write_all(&data)?,
+        #[cfg(feature = "compression")]
+        DataSource::Compressed(codec, level, _, Some(source), None) => {
+            let mut encoder = codec.encoder(level, &mut writer)?;
+            match *source {
+                DataSource::File(f, length) => {
+                    if std::io::copy(&mut f.take(length), &mut encoder)? != length {
+                        return Err(DataSource::content_changed(None));
+                    }
+                }
+                DataSource::Owned(data) => encoder.write_all(&data)?,
+                DataSource::Ref(data) => encoder.write_all(data)?,
+                DataSource::Path(path, length) => {
+                    if std::io::copy(&mut open_path(&path, length)?, &mut encoder)? != length {
+                        return Err(DataSource::path_changed(&path));
+                    }
+                }
+                DataSource::Range(range) if !range.is_compressed() => {
+                    std::io::copy(&mut range.stream()?, &mut encoder)?;
+                }
+                DataSource::Reader(_) => return Err(hff_core::write::Stream::unknown_length()),
+                _ => {
+                    return Err(hff_core::Error::Invalid(
+                        "Compressed content can't be compressed again.".into(),
+                    ))
+                }
+            }
+            encoder.finish()?;
+        }
+        #[cfg(feature = "compression")]
+        DataSource::Compressed(_, _, _, None, None) => {
+            return Err(hff_core::Error::Invalid(
+                "Internal error dealing with compressed data.".into(),
+            ))
+        }
+    }
+    Ok((writer.length, writer.hasher.finish()))
+}
 
-        // Record the offset and length.
-        offset_len.push((offset as u64, length));
+/// Open the file of a path data source limited to its length.
+fn open_path(path: &std::path::Path, length: u64) -> Result<std::io::Take<std::fs::File>> {
+    Ok(DataSource::open_path(path, length)?.take(length))
+}
 
-        // What is the padding requirement?
-        let padding = (length.next_multiple_of(16) - length) as usize;
-        // Track where we are in the output stream.
-        offset += length as usize + padding;
+/// Passes data through to the writer while updating the checksum
+/// and counting the bytes written.
+struct ChecksumWriter<'a> {
+    writer: &'a mut dyn Write,
+    hasher: ChecksumHasher,
+    length: u64,
+}
 
-        // Write the padding.
-        let padding = vec![0; padding];
-        writer.write_all(&padding)?;
+impl<'a> Write for ChecksumWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let count = self.writer.write(buf)?;
+        self.hasher.update(&buf[..count]);
+        self.length += count as u64;
+        Ok(count)
     }
 
-    Ok(offset_len)
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::*;
+    use super::write_data_array;
+    use crate::{
+        test_support::{checks, test_table, TempPath},
+        *,
+    };
     use hff_core::{
-        write::{chunk, hff, table},
+        write::{chunk, hff, table, DataArray},
         IdType,
     };
 
@@ -158,6 +365,86 @@ mod tests {
         let _hff = crate::read::inspect(&mut buffer.as_slice()).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "compression")]
+    fn lazy_compressed() {
+        let text = "Compressed straight into the output stream.".repeat(100);
+        let path = TempPath::new("lazy_compressed.txt");
+        std::fs::write(&path, &text).unwrap();
+        let content = || {
+            hff([table((Ecc::new("Lazy"), Ecc::INVALID))
+                .metadata((9, text.as_str()))
+                .unwrap()
+                .chunks([
+                    chunk((Ecc::new("File"), Ecc::INVALID), (9, &*path)).unwrap(),
+                    chunk((Ecc::new("Owned"), Ecc::INVALID), (9, text.as_str())).unwrap(),
+                ])])
+        };
+
+        let mut lazy = std::io::Cursor::new(vec![]);
+        content()
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        let mut buffered = vec![];
+        content()
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffered)
+            .unwrap();
+
+        // Streaming the compression produces exactly what buffering does.
+        assert_eq!(lazy.get_ref(), &buffered);
+        let hff = crate::read::read_validated(&mut lazy.get_ref().as_slice()).unwrap();
+        let root = hff.tables().next().unwrap();
+        assert_eq!(hff.get_decompressed(&root).unwrap(), text.as_bytes());
+        for chunk in root.chunks() {
+            assert!(chunk.size() < text.len());
+            assert_eq!(hff.get_decompressed(&chunk).unwrap(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn changed_file() {
+        let original = "Original content!";
+        let path = TempPath::new("changed_file.txt");
+        std::fs::write(&path, original).unwrap();
+        let content = || {
+            let file =
+                || DataSource::file(std::fs::File::open(&path).unwrap(), original.len() as u64);
+            [
+                file(),
+                #[cfg(feature = "compression")]
+                DataSource::compressed(9, file()),
+            ]
+            .map(|source| hff([table((Ecc::new("File"), Ecc::INVALID)).metadata_source(source)]))
+        };
+
+        // Files which shrink can't provide the recorded length.
+        std::fs::write(&path, "Short.").unwrap();
+        for desc in content() {
+            let result = desc.lazy_write::<hff_core::LE>(
+                IdType::Ecc2,
+                "Test",
+                &mut std::io::Cursor::new(vec![]),
+            );
+            assert!(matches!(result, Err(Error::Invalid(message)) if message.contains("changed")));
+        }
+        for desc in content() {
+            let result = desc.write::<hff_core::LE>(IdType::Ecc2, "Test", &mut vec![]);
+            assert!(matches!(result, Err(Error::Invalid(_))));
+        }
+
+        // Files which grow are only read up to the recorded length.
+        std::fs::write(&path, "Original content! And more.").unwrap();
+        for desc in content() {
+            let mut lazy = std::io::Cursor::new(vec![]);
+            desc.lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+                .unwrap();
+            let hff = crate::read::read_validated(&mut lazy.get_ref().as_slice()).unwrap();
+            assert!(hff.verify().is_empty());
+            let root = hff.tables().next().unwrap();
+            assert_eq!(hff.get_decompressed(&root).unwrap(), original.as_bytes());
+        }
+    }
+
     #[test]
     fn test() {
         let content = hff([
@@ -207,4 +494,727 @@ mod tests {
 
         //assert!(false);
     }
+
+    #[test]
+    fn changed_path() {
+        let path = TempPath::new("changed_path.txt");
+        std::fs::write(&path, "Original content.").unwrap();
+        let mut data = DataArray::new();
+        data.push(DataSource::path(&*path).unwrap());
+        let offset_len = data.prepare(Checksum::Crc32c).unwrap();
+
+        // Same length, different content.
+        std::fs::write(&path, "Modified content.").unwrap();
+        let result = write_data_array(data, Checksum::Crc32c, Some(&offset_len), &mut vec![]);
+        assert!(matches!(result, Err(Error::Invalid(message)) if message.contains("changed")));
+    }
+
+    #[test]
+    fn verify() {
+        // A file backed chunk to make sure checksumming during prepare
+        // leaves the file ready to be written.
+        let path = TempPath::new("verify.txt");
+        std::fs::write(&path, "Chunk content supplied from a file.").unwrap();
+
+        for checksum in [Checksum::Crc32c, Checksum::XxHash64] {
+            let content = hff([table((Ecc::new("Test"), Ecc::new("TestSub")))
+                .metadata("Some metadata.")
+                .unwrap()
+                .chunks([
+                    chunk((Ecc::new("C0"), Ecc::new("S0")), "First chunk.").unwrap(),
+                    chunk((Ecc::new("C1"), Ecc::new("S1")), &*path).unwrap(),
+                    chunk((Ecc::new("C2"), Ecc::new("S2")), "Last chunk.").unwrap(),
+                ])])
+            .with_checksum(checksum);
+            let mut buffer = vec![];
+            content
+                .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+                .unwrap();
+
+            let hff = crate::read::read(&mut buffer.as_slice()).unwrap();
+            assert!(hff.verify().is_empty());
+            assert!(hff
+                .chunks_array()
+                .iter()
+                .all(|c| c.checksum_type() == checksum as u32));
+            let (offset, identifier) = {
+                let chunk = hff.tables().next().unwrap().chunks().nth(1).unwrap();
+                assert!(chunk.verify());
+                assert_eq!(
+                    hff.read(&chunk).unwrap(),
+                    b"Chunk content supplied from a file."
+                );
+                (chunk.offset(), chunk.identifier())
+            };
+
+            // Flip a bit in the middle chunk and make sure only it is reported.
+            buffer[offset as usize + 3] ^= 0x10;
+            let hff = crate::read::open(std::io::Cursor::new(buffer)).unwrap();
+            assert_eq!(
+                hff.verify(),
+                vec![hff_core::read::Corrupt::Chunk {
+                    chunk: 1,
+                    identifier
+                }]
+            );
+            let root = hff.tables().next().unwrap();
+            assert!(root.verify());
+            let verified = root.chunks().map(|c| c.verify()).collect::<Vec<_>>();
+            assert_eq!(verified, vec![true, false, true]);
+        }
+    }
+
+    #[test]
+    fn graft() {
+        let mut buffer = vec![];
+        test_table()
+            .unwrap()
+            .with_alignment(64)
+            .unwrap()
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            .unwrap();
+        let source = crate::read::open(std::io::Cursor::new(buffer)).unwrap();
+        let (_, root) = source.depth_first().next().unwrap();
+
+        // Graft the whole structure and part of it next to new content.
+        let content = || {
+            hff([
+                crate::graft(&root).unwrap(),
+                table((Ecc::new("New"), Ecc::INVALID))
+                    .chunks([chunk((Ecc::new("Chunk"), Ecc::INVALID), "New content.").unwrap()])
+                    .children([crate::graft(&root.iter().next().unwrap()).unwrap()]),
+            ])
+        };
+        let mut written = vec![];
+        content()
+            .write::<hff_core::BE>(IdType::Ecc2, "Test", &mut written)
+            .unwrap();
+        let mut lazy = std::io::Cursor::new(vec![]);
+        content()
+            .with_parallel(true)
+            .lazy_write::<hff_core::BE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        assert_eq!(lazy.get_ref(), &written);
+        let mut streamed = vec![];
+        content()
+            .stream_write::<hff_core::BE>(IdType::Ecc2, "Test", &mut streamed)
+            .unwrap();
+
+        for buffer in [&written, &streamed] {
+            let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+            assert!(hff.verify().is_empty());
+            checks(&hff);
+
+            // Content is copied as stored.
+            let copied = hff.tables().next().unwrap();
+            for (from, to) in root.chunks().zip(copied.chunks()) {
+                assert_eq!(from.identifier(), to.identifier());
+                assert_eq!(from.codec().unwrap(), to.codec().unwrap());
+                assert_eq!(from.alignment(), to.alignment());
+                assert_eq!(source.get(&from).unwrap(), hff.read(&to).unwrap());
+            }
+
+            let child = hff.find(&[
+                (Ecc::new("New"), Ecc::INVALID),
+                (Ecc::new("C0Prime"), Ecc::new("C0Sub")),
+            ]);
+            let child = child.table().unwrap();
+            assert_eq!(
+                hff.read(&child).unwrap(),
+                "Each table has its own metadata.".as_bytes()
+            );
+            assert_eq!(child.iter().count(), 2);
+        }
+    }
+
+    #[test]
+    fn reader() {
+        use std::io::Read;
+
+        // A source which only hands out a few bytes at a time.
+        let text = "Streamed content of a length only known once it is read.";
+        let stream = || {
+            DataSource::reader(
+                text.as_bytes()[..20]
+                    .chain(&text.as_bytes()[20..40])
+                    .chain(&text.as_bytes()[40..]),
+            )
+        };
+        let content = |parallel| {
+            hff([table((Ecc::new("Root"), Ecc::INVALID))
+                .metadata_source(stream())
+                .chunks([
+                    ChunkDesc::new((Ecc::new("Stream"), Ecc::INVALID).into(), stream()),
+                    chunk((Ecc::new("Text"), Ecc::INVALID), "Known length.").unwrap(),
+                ])])
+            .with_parallel(parallel)
+        };
+
+        let mut lazy = std::io::Cursor::new(vec![]);
+        content(true)
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        let mut streamed = vec![];
+        content(false)
+            .stream_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut streamed)
+            .unwrap();
+        for buffer in [lazy.get_ref(), &streamed] {
+            let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+            assert!(hff.verify().is_empty());
+            let root = hff.tables().next().unwrap();
+            assert_eq!(hff.read(&root).unwrap(), text.as_bytes());
+            let chunks = root
+                .chunks()
+                .map(|c| hff.read(&c).unwrap().to_vec())
+                .collect::<Vec<_>>();
+            assert_eq!(chunks, vec![text.as_bytes(), "Known length.".as_bytes()]);
+        }
+
+        // The incremental writer streams it as well.
+        let mut writer = HffWriter::new::<hff_core::LE>(vec![], IdType::Ecc2, "Test").unwrap();
+        writer.open_table((Ecc::new("Root"), Ecc::INVALID)).unwrap();
+        let boxed: Box<dyn Read + Send> = Box::new(text.as_bytes());
+        writer
+            .chunk((Ecc::new("Stream"), Ecc::INVALID), boxed)
+            .unwrap();
+        writer.close_table().unwrap();
+        let buffer = writer.finish().unwrap();
+        let file = crate::read::read(&mut buffer.as_slice()).unwrap();
+        let chunk = file.tables().next().unwrap().chunks().next().unwrap();
+        assert_eq!(file.read(&chunk).unwrap(), text.as_bytes());
+
+        // Writing without seeking needs every length up front.
+        let mut buffer = vec![];
+        let result = content(false).write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer);
+        assert!(matches!(result, Err(Error::Invalid(_))));
+        assert!(buffer.is_empty());
+        let result = content(true).write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer);
+        assert!(matches!(result, Err(Error::Invalid(_))));
+        assert!(buffer.is_empty());
+
+        // As does deduplication.
+        let result = content(false).with_dedup(true).lazy_write::<hff_core::LE>(
+            IdType::Ecc2,
+            "Test",
+            &mut std::io::Cursor::new(vec![]),
+        );
+        assert!(matches!(result, Err(Error::Invalid(_))));
+
+        // Compression records the uncompressed length up front, whether
+        // compressed in advance or while writing.
+        #[cfg(feature = "compression")]
+        for parallel in [false, true] {
+            let result = hff([table((Ecc::new("Root"), Ecc::INVALID))
+                .metadata_source(DataSource::compressed(6, stream()))])
+            .with_parallel(parallel)
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut std::io::Cursor::new(vec![]));
+            assert!(
+                matches!(result, Err(Error::Invalid(message)) if message.contains("length of reader content"))
+            );
+        }
+    }
+
+    #[test]
+    fn paths() {
+        // More files than can usually be held open at once.
+        let dir = TempPath::new("paths");
+        std::fs::create_dir_all(&dir).unwrap();
+        let count = 2000;
+        let paths = (0..count)
+            .map(|index| {
+                let path = dir.join(format!("{}.txt", index));
+                std::fs::write(&path, format!("File {}.", index)).unwrap();
+                path
+            })
+            .collect::<Vec<_>>();
+        let content = || {
+            let chunks = paths
+                .iter()
+                .map(|path| chunk((Ecc::new("File"), Ecc::INVALID), path.as_path()).unwrap());
+            hff([table((Ecc::new("Files"), Ecc::INVALID)).chunks(chunks)])
+        };
+
+        let mut buffer = vec![];
+        content()
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            .unwrap();
+        let mut lazy = std::io::Cursor::new(vec![]);
+        content()
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        assert_eq!(lazy.get_ref(), &buffer);
+        let file = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+        assert!(file.verify().is_empty());
+        let files = file.tables().next().unwrap();
+        assert_eq!(files.chunk_count(), count);
+        for (index, chunk) in files.chunks().enumerate() {
+            assert_eq!(
+                file.read(&chunk).unwrap(),
+                format!("File {}.", index).as_bytes()
+            );
+        }
+
+        // Files are only opened when written, changes in between are errors.
+        let path = dir.join("changed.txt");
+        std::fs::write(&path, "Original.").unwrap();
+        let source = DataSource::path(&path).unwrap();
+        std::fs::write(&path, "Changed after adding.").unwrap();
+        let result = hff([
+            table((Ecc::new("File"), Ecc::INVALID)).chunks([ChunkDesc::new(
+                (Ecc::new("File"), Ecc::INVALID).into(),
+                source,
+            )]),
+        ])
+        .lazy_write::<hff_core::LE>(
+            IdType::Ecc2,
+            "Test",
+            &mut std::io::Cursor::new(vec![]),
+        );
+        assert!(matches!(result, Err(Error::Invalid(_))));
+        let source = DataSource::path(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let result = hff([table((Ecc::new("File"), Ecc::INVALID)).metadata_source(source)])
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut vec![]);
+        assert!(matches!(result, Err(Error::IoError(_))));
+        assert!(DataSource::path(&path).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn codecs() {
+        let text = "Each chunk records the codec it was compressed with.".repeat(8);
+        let codecs = [Codec::None, Codec::Xz, Codec::Zstd, Codec::Lz4]
+            .into_iter()
+            .filter(|codec| codec.is_available())
+            .collect::<Vec<_>>();
+
+        let chunks = codecs.iter().map(|&codec| {
+            let source = DataSource::owned(text.as_bytes());
+            ChunkDesc::new(
+                (Ecc::new("Codec"), Ecc::INVALID).into(),
+                DataSource::compressed_with(codec, 6, source),
+            )
+        });
+        let content = hff([table((Ecc::new("Codecs"), Ecc::INVALID))
+            .metadata((Codec::Xz, 6, text.as_str()))
+            .unwrap()
+            .chunks(chunks)]);
+
+        let mut buffer = vec![];
+        content
+            .write::<hff_core::NE>(IdType::Ecc2, "Test", &mut buffer)
+            .unwrap();
+        let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+
+        // Readers find the codec in the file, no out of band information needed.
+        let root = hff.tables().next().unwrap();
+        assert_eq!(root.codec().unwrap(), Codec::Xz);
+        assert_eq!(hff.get_decompressed(&root).unwrap(), text.as_bytes());
+        for (chunk, codec) in root.chunks().zip(codecs) {
+            assert_eq!(chunk.codec().unwrap(), codec);
+            assert_eq!(chunk.uncompressed_len(), text.len() as u64);
+            assert_eq!(hff.get_decompressed(&chunk).unwrap(), text.as_bytes());
+        }
+    }
+
+    #[test]
+    fn parallel() {
+        // Parallel preparation must not change a single byte of the output.
+        let write = |parallel: bool| {
+            let mut buffer = vec![];
+            test_table()
+                .unwrap()
+                .with_checksum(Checksum::XxHash64)
+                .with_parallel(parallel)
+                .write::<hff_core::NE>(IdType::Ecc2, "Test", &mut buffer)
+                .unwrap();
+            buffer
+        };
+        let lazy_write = |parallel: bool| {
+            let mut buffer = std::io::Cursor::new(vec![]);
+            test_table()
+                .unwrap()
+                .with_checksum(Checksum::XxHash64)
+                .with_parallel(parallel)
+                .lazy_write::<hff_core::NE>(IdType::Ecc2, "Test", &mut buffer)
+                .unwrap();
+            buffer.into_inner()
+        };
+
+        let sequential = write(false);
+        assert_eq!(write(true), sequential);
+        assert_eq!(lazy_write(true), sequential);
+        assert_eq!(lazy_write(false), sequential);
+
+        let hff = crate::read::read_validated(&mut sequential.as_slice()).unwrap();
+        assert!(hff.verify().is_empty());
+        checks(&hff);
+    }
+
+    #[test]
+    fn stream_write() {
+        use hff_core::{read::ContentReader, Footer};
+
+        fn same<T: std::fmt::Debug + ContentReader>(hff: &Hff<T>, cached: &Hff<ChunkCache>) {
+            assert_eq!(hff.tables_array(), cached.tables_array());
+            assert_eq!(hff.chunks_array(), cached.chunks_array());
+            for (_, table) in hff.depth_first() {
+                if table.has_metadata() {
+                    assert_eq!(
+                        hff.get_decompressed(&table).unwrap(),
+                        cached.get_decompressed(&table).unwrap()
+                    );
+                }
+                for chunk in table.chunks() {
+                    assert_eq!(
+                        hff.get_decompressed(&chunk).unwrap(),
+                        cached.get_decompressed(&chunk).unwrap()
+                    );
+                }
+            }
+        }
+
+        for big_endian in [false, true] {
+            // Write through a plain Write with no Seek.
+            let mut buffer = vec![];
+            let content = test_table().unwrap().with_checksum(Checksum::XxHash64);
+            if big_endian {
+                content.stream_write::<hff_core::BE>(IdType::Ecc2, "Test", &mut buffer)
+            } else {
+                content.stream_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            }
+            .unwrap();
+
+            // Every reader finds the structure through the footer.
+            let cached = crate::read::read(&mut buffer.as_slice()).unwrap();
+            checks(&cached);
+            assert!(cached.verify().is_empty());
+            checks(&crate::read::read_validated(&mut buffer.as_slice()).unwrap());
+            let inspected = crate::read::inspect(&mut buffer.as_slice()).unwrap();
+            assert_eq!(inspected.tables_array(), cached.tables_array());
+            assert_eq!(inspected.chunks_array(), cached.chunks_array());
+
+            let opened = crate::read::open(std::io::Cursor::new(buffer.clone())).unwrap();
+            let validated =
+                crate::read::open_validated(std::io::Cursor::new(buffer.clone())).unwrap();
+            let path = TempPath::new("stream_write.hff");
+            std::fs::write(&path, &buffer).unwrap();
+            let mapped = crate::read::map(&path).unwrap();
+            let file = crate::read::open_file(&path).unwrap();
+            same(&opened, &cached);
+            same(&validated, &cached);
+            same(&mapped, &cached);
+            same(&file, &cached);
+
+            // A footer pointing anywhere but the end of the arrays is rejected.
+            let mut bad = buffer.clone();
+            let at = bad.len() - Footer::SIZE + 8;
+            bad[at] ^= 0x10;
+            assert!(matches!(
+                crate::read::read_validated(&mut bad.as_slice()),
+                Err(Error::InvalidStructure(_))
+            ));
+            assert!(matches!(
+                crate::read::read(&mut buffer[..buffer.len() - 8].as_ref()),
+                Err(Error::MissingFooter)
+            ));
+            assert!(matches!(
+                crate::read::read(&mut buffer[..hff_core::Header::SIZE + 8].as_ref()),
+                Err(Error::Truncated {
+                    section: Section::Footer
+                })
+            ));
+        }
+    }
+
+    #[test]
+    fn dedup() {
+        let payload = "A payload shared between several tables.".repeat(4);
+        let content = |dedup: bool| {
+            hff([
+                table((Ecc::new("First"), Ecc::INVALID))
+                    .metadata(payload.as_str())
+                    .unwrap()
+                    .chunks([
+                        chunk((Ecc::new("C0"), Ecc::INVALID), payload.as_str()).unwrap(),
+                        chunk((Ecc::new("C1"), Ecc::INVALID), "Unique content.").unwrap(),
+                    ]),
+                table((Ecc::new("Second"), Ecc::INVALID)).chunks([
+                    chunk((Ecc::new("C0"), Ecc::INVALID), payload.as_str()).unwrap(),
+                    #[cfg(feature = "compression")]
+                    chunk((Ecc::new("C1"), Ecc::INVALID), (9, payload.as_str())).unwrap(),
+                    #[cfg(feature = "compression")]
+                    chunk((Ecc::new("C2"), Ecc::INVALID), (9, payload.as_str())).unwrap(),
+                ]),
+            ])
+            .with_checksum(Checksum::Crc32c)
+            .with_dedup(dedup)
+        };
+
+        let mut plain = vec![];
+        content(false)
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut plain)
+            .unwrap();
+        let mut deduped = vec![];
+        let written = content(true)
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut deduped)
+            .unwrap();
+        let mut lazy = std::io::Cursor::new(vec![]);
+        let lazy_written = content(true)
+            .with_parallel(true)
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        assert_eq!(lazy.get_ref(), &deduped);
+        let mut streamed = vec![];
+        let streamed_written = content(true)
+            .stream_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut streamed)
+            .unwrap();
+
+        // The duplicates point at the first copy of the content.
+        let plain_length = plain.len() as u64;
+        let plain = crate::read::read_validated(&mut plain.as_slice()).unwrap();
+        assert_eq!(plain.dedup_stats().shared, 0);
+        let shared = if cfg!(feature = "compression") { 3 } else { 2 };
+        let compressed = plain.chunks_array().get(3).map(|c| c.length()).unwrap_or(0);
+        let saved = payload.len() as u64 * 2 + compressed * (shared - 2);
+        let padded = (payload.len() as u64).next_multiple_of(16) * 2
+            + compressed.next_multiple_of(16) * (shared - 2);
+        assert_eq!(deduped.len() as u64, plain_length - padded);
+        for buffer in [&deduped, &streamed] {
+            let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+            assert!(hff.verify().is_empty());
+            let stats = hff.dedup_stats();
+            assert_eq!(stats.entries, plain.dedup_stats().entries);
+            assert_eq!(stats.shared, shared as usize);
+            assert_eq!(stats.bytes_saved, saved);
+
+            // The writers report the same statistics as the result.
+            assert_eq!(stats, written);
+            assert_eq!(stats, lazy_written);
+            assert_eq!(stats, streamed_written);
+
+            for ((_, table), (_, plain_table)) in hff.depth_first().zip(plain.depth_first()) {
+                if table.has_metadata() {
+                    assert_eq!(hff.read(&table).unwrap(), plain.read(&plain_table).unwrap());
+                }
+                for (chunk, plain_chunk) in table.chunks().zip(plain_table.chunks()) {
+                    assert_eq!(
+                        hff.get_decompressed(&chunk).unwrap(),
+                        plain.get_decompressed(&plain_chunk).unwrap()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn alignment() {
+        let content = || {
+            hff([table((Ecc::new("Aligned"), Ecc::INVALID))
+                .metadata("Metadata aligned to the file.")
+                .unwrap()
+                .chunks([
+                    chunk((Ecc::new("Page"), Ecc::INVALID), "File aligned.").unwrap(),
+                    chunk((Ecc::new("Small"), Ecc::INVALID), "Chunk aligned.")
+                        .unwrap()
+                        .aligned(100)
+                        .unwrap(),
+                    chunk((Ecc::new("Large"), Ecc::INVALID), "Chunk aligned.")
+                        .unwrap()
+                        .aligned(8192)
+                        .unwrap(),
+                ])])
+            .with_alignment(4096)
+            .unwrap()
+        };
+
+        let mut buffer = vec![];
+        content()
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            .unwrap();
+        let mut lazy = std::io::Cursor::new(vec![]);
+        content()
+            .with_parallel(true)
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut lazy)
+            .unwrap();
+        assert_eq!(lazy.get_ref(), &buffer);
+        let mut streamed = vec![];
+        content()
+            .stream_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut streamed)
+            .unwrap();
+
+        for buffer in [&buffer, &streamed] {
+            let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+            let (_, table) = hff.depth_first().next().unwrap();
+            assert_eq!(table.metadata_alignment(), 4096);
+            assert_eq!(table.offset() % 4096, 0);
+            assert_eq!(
+                hff.read(&table).unwrap(),
+                "Metadata aligned to the file.".as_bytes()
+            );
+
+            let alignments = table.chunks().map(|c| c.alignment()).collect::<Vec<_>>();
+            assert_eq!(alignments, vec![4096, 128, 8192]);
+            for chunk in table.chunks() {
+                assert_eq!(chunk.offset() % chunk.alignment() as u64, 0);
+            }
+            let content = table
+                .chunks()
+                .map(|c| hff.read(&c).unwrap().to_vec())
+                .collect::<Vec<_>>();
+            assert_eq!(content[0], "File aligned.".as_bytes());
+            assert_eq!(content[2], "Chunk aligned.".as_bytes());
+        }
+
+        // Editing keeps the alignment of untouched content.
+        let mut edited = vec![];
+        HffEditor::open(std::io::Cursor::new(buffer.clone()))
+            .unwrap()
+            .write::<hff_core::LE>(&mut edited)
+            .unwrap();
+        assert_eq!(edited, buffer);
+
+        // Replaced content keeps its alignment and encoding, new content
+        // takes what it is given.
+        let mut editor = HffEditor::open(std::io::Cursor::new(buffer.clone())).unwrap();
+        let table = editor.table_mut(&[0]).unwrap();
+        table.set_chunk_encoding(1, Encoding::Json).unwrap();
+        table.replace_chunk(1, "\"Replaced.\"").unwrap();
+        table.set_metadata("Replaced metadata.").unwrap();
+        table.add_chunk_desc(
+            chunk((Ecc::new("Added"), Ecc::INVALID), "\"Added.\"")
+                .unwrap()
+                .with_encoding(Encoding::Json)
+                .aligned(1024)
+                .unwrap(),
+        );
+        table
+            .add_chunk((Ecc::new("Plain"), Ecc::INVALID), "Plain.")
+            .unwrap();
+        table.set_chunk_alignment(4, 64).unwrap();
+        assert!(matches!(
+            table.set_chunk_alignment(4, u32::MAX),
+            Err(Error::Invalid(_))
+        ));
+        assert!(matches!(
+            table.set_metadata_alignment(1 << 31),
+            Err(Error::Invalid(_))
+        ));
+        let mut edited = vec![];
+        editor.write::<hff_core::LE>(&mut edited).unwrap();
+
+        let hff = crate::read::read_validated(&mut edited.as_slice()).unwrap();
+        let (_, table) = hff.depth_first().next().unwrap();
+        assert_eq!(table.metadata_alignment(), 4096);
+        assert_eq!(table.offset() % 4096, 0);
+        let chunks = table.chunks().collect::<Vec<_>>();
+        let alignments = chunks.iter().map(|c| c.alignment()).collect::<Vec<_>>();
+        assert_eq!(alignments, vec![4096, 128, 8192, 1024, 64]);
+        for chunk in &chunks {
+            assert_eq!(chunk.offset() % chunk.alignment() as u64, 0);
+        }
+        let encodings = chunks
+            .iter()
+            .map(|c| c.encoding().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(encodings[1], Encoding::Json);
+        assert_eq!(encodings[3], Encoding::Json);
+        assert_eq!(encodings[4], Encoding::None);
+        assert_eq!(hff.read(&chunks[1]).unwrap(), "\"Replaced.\"".as_bytes());
+        assert_eq!(hff.read(&chunks[3]).unwrap(), "\"Added.\"".as_bytes());
+        assert_eq!(hff.read(&table).unwrap(), "Replaced metadata.".as_bytes());
+
+        // Alignments which aren't a power of two are rejected.
+        use hff_core::{Chunk, Header, Table};
+        let alignment = Header::SIZE + Table::SIZE + 56;
+        let mut bad = buffer.clone();
+        bad[alignment..alignment + 4].copy_from_slice(&24_u32.to_le_bytes());
+        assert!(matches!(
+            crate::read::read_validated(&mut bad.as_slice()),
+            Err(Error::InvalidStructure(_))
+        ));
+
+        // As are alignments which can't be rounded up or would mostly pad.
+        let desc = || chunk((Ecc::new("Chunk"), Ecc::INVALID), "Content.").unwrap();
+        assert_eq!(
+            desc().aligned(Chunk::MAX_ALIGNMENT).unwrap().alignment(),
+            Some(Chunk::MAX_ALIGNMENT)
+        );
+        for alignment in [Chunk::MAX_ALIGNMENT + 1, 1 << 31, u32::MAX] {
+            assert!(matches!(desc().aligned(alignment), Err(Error::Invalid(_))));
+            assert!(matches!(
+                crate::hff([]).with_alignment(alignment),
+                Err(Error::Invalid(_))
+            ));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Config {
+            name: String,
+            scale: f32,
+            layers: Vec<u32>,
+            parent: Option<String>,
+        }
+
+        let config = Config {
+            name: "Terrain".into(),
+            scale: 0.5,
+            layers: vec![1, 2, 4],
+            parent: None,
+        };
+        let encodings = [
+            Encoding::Bincode,
+            Encoding::Cbor,
+            Encoding::Json,
+            Encoding::MessagePack,
+        ];
+        let content = hff([table((Ecc::new("Config"), Ecc::INVALID))
+            .metadata_serde(&config, Encoding::Json)
+            .unwrap()
+            .chunks(
+                encodings
+                    .iter()
+                    .map(|&encoding| {
+                        chunk_serde((Ecc::new("Config"), Ecc::INVALID), &config, encoding).unwrap()
+                    })
+                    .chain([chunk((Ecc::new("Raw"), Ecc::INVALID), "Not encoded.").unwrap()]),
+            )]);
+
+        let mut buffer = vec![];
+        content
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            .unwrap();
+        let hff = crate::read::read_validated(&mut buffer.as_slice()).unwrap();
+
+        let (_, table) = hff.depth_first().next().unwrap();
+        assert_eq!(table.encoding().unwrap(), Encoding::Json);
+        assert_eq!(table.metadata_as::<Config>().unwrap(), config);
+        let json = std::str::from_utf8(hff.read(&table).unwrap()).unwrap();
+        assert!(json.contains("\"name\":\"Terrain\""));
+
+        let chunks = table.chunks().collect::<Vec<_>>();
+        for (chunk, encoding) in chunks.iter().zip(encodings) {
+            assert_eq!(chunk.encoding().unwrap(), encoding);
+            assert_eq!(chunk.decode::<Config>().unwrap(), config);
+        }
+
+        // Raw content is never guessed at.
+        let raw = chunks.last().unwrap();
+        assert_eq!(raw.encoding().unwrap(), Encoding::None);
+        assert!(raw.decode::<String>().is_err());
+
+        // The encodings survive editing.
+        let mut edited = vec![];
+        HffEditor::open(std::io::Cursor::new(buffer.clone()))
+            .unwrap()
+            .write::<hff_core::LE>(&mut edited)
+            .unwrap();
+        assert_eq!(edited, buffer);
+    }
 }
@@ -6,12 +6,16 @@ pub use hff_core::{
     read::{ChunkView, Hff, TableView},
     utilities,
     write::{chunk, hff, table, ChunkDesc, DataSource, HffDesc, TableBuilder},
-    ByteOrder, ChunkCache, ContentInfo, Ecc, Error, IdType, Result, Version, BE, LE, NE, OP,
+    ByteOrder, Checksum, ChunkCache, Codec, ContentInfo, Ecc, Error, IdType, Result, Section,
+    Version, BE, LE, NE, OP,
 };
 
 mod read;
 pub use read::*;
 
+mod write;
+pub use write::*;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -38,10 +42,11 @@ mod tests {
             table((Ecc::new("Child2"), Ecc::INVALID)),
         ]);
 
-        // Use std variation to write into a vector.
+        // Write into a vector.
         let mut buffer = vec![];
-        use hff_std::Writer;
-        content.write::<hff_core::NE>(IdType::Ecc2, "Test", &mut buffer)?;
+        content
+            .write::<hff_core::NE>(IdType::Ecc2, "Test", &mut buffer)
+            .await?;
 
         // The reader must take ownership of the given item in order to
         // properly function.
@@ -69,6 +74,129 @@ mod tests {
             }
         }
 
+        Ok(())
+    }
+    #[tokio::test]
+    async fn lazy_write() -> Result<()> {
+        let content = hff([table((Ecc::new("Prime"), Ecc::new("Second")))
+            .metadata("Lazy written metadata.")?
+            .chunks([
+                chunk((Ecc::new("Chunk0"), Ecc::INVALID), "The first chunk.")?,
+                chunk((Ecc::new("Chunk1"), Ecc::INVALID), "The second chunk.")?,
+            ])]);
+
+        // Lazy write into an async cursor which supports seeking.
+        let mut writer = std::io::Cursor::new(vec![]);
+        content
+            .lazy_write::<hff_core::OP>(IdType::Ecc2, "Test", &mut writer)
+            .await?;
+
+        // Read it back and check the content.
+        let buffer = writer.into_inner();
+        let hff = read(&mut buffer.as_slice()).await?;
+        let root = hff.tables().next().unwrap();
+        assert_eq!(hff.read(&root)?, "Lazy written metadata.".as_bytes());
+        let chunks = root
+            .chunks()
+            .map(|c| hff.read(&c).map(|d| d.to_vec()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            chunks,
+            vec![
+                "The first chunk.".as_bytes().to_vec(),
+                "The second chunk.".as_bytes().to_vec()
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn blocking_sources() -> Result<()> {
+        // Synchronous sources are read through block_in_place on a
+        // multi threaded runtime.
+        let text = "Read from a synchronous stream.";
+        let content = || {
+            Ok::<_, Error>(hff([table((Ecc::new("Prime"), Ecc::INVALID))
+                .metadata_source(DataSource::reader(text.as_bytes()))
+                .chunks([
+                    chunk((Ecc::new("Chunk0"), Ecc::INVALID), text)?,
+                    #[cfg(feature = "compression")]
+                    chunk((Ecc::new("Chunk1"), Ecc::INVALID), (9, text))?,
+                ])]))
+        };
+
+        let mut writer = std::io::Cursor::new(vec![]);
+        content()?
+            .lazy_write::<hff_core::LE>(IdType::Ecc2, "Test", &mut writer)
+            .await?;
+        let buffer = writer.into_inner();
+        let file = read(&mut buffer.as_slice()).await?;
+        let root = file.tables().next().unwrap();
+        assert_eq!(file.read(&root)?, text.as_bytes());
+        for chunk in root.chunks() {
+            assert_eq!(file.get_decompressed(&chunk)?, text.as_bytes());
+        }
+
+        // Grafted content is read back through its range.
+        let mut grafted = vec![];
+        hff([hff_core::write::graft(&root)?])
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut grafted)
+            .await?;
+        assert_eq!(grafted, buffer);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn errors() -> Result<()> {
+        use hff_core::{Header, Table};
+
+        let content = hff([table((Ecc::new("Prime"), Ecc::INVALID))
+            .metadata("Some metadata.")?
+            .chunks([chunk((Ecc::new("Chunk"), Ecc::INVALID), "A chunk.")?])]);
+        let mut buffer = vec![];
+        content
+            .write::<hff_core::LE>(IdType::Ecc2, "Test", &mut buffer)
+            .await?;
+
+        let mut bad = buffer.clone();
+        bad[0] ^= 0xff;
+        assert!(matches!(
+            read(&mut bad.as_slice()).await,
+            Err(Error::BadMagic)
+        ));
+        assert!(matches!(
+            inspect(&mut &buffer[..Header::SIZE - 1]).await,
+            Err(Error::Truncated {
+                section: Section::Header
+            })
+        ));
+        assert!(matches!(
+            read(&mut &buffer[..Header::SIZE + Table::SIZE - 1]).await,
+            Err(Error::Truncated {
+                section: Section::Tables
+            })
+        ));
+
+        // The footer layout is only read by hff-std.
+        let footer = Header::footer(IdType::Ecc2, Ecc::new("Test")).to_bytes::<hff_core::LE>()?;
+        assert!(matches!(
+            inspect(&mut footer.as_slice()).await,
+            Err(Error::UnsupportedLayout)
+        ));
+
+        // Content past the end of the stream.
+        let truncated = buffer[..buffer.len() - 16].to_vec();
+        let hff = open(Box::new(std::io::Cursor::new(truncated)) as Box<dyn ReadSeek>).await?;
+        let chunk = hff.tables().next().unwrap().chunks().next().unwrap();
+        assert!(matches!(
+            hff.read(&chunk).await,
+            Err(Error::Truncated {
+                section: Section::Content
+            })
+        ));
+
         Ok(())
     }
 }
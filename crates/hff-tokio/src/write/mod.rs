@@ -0,0 +1,307 @@
+use async_trait::async_trait;
+use core::marker::Unpin;
+use hff_core::{
+    read::DedupStats,
+    write::{DataArray, DataSource, HffDesc},
+    ByteOrder, Checksum, Chunk, ContentInfo, Ecc, Header, IdType, Result,
+};
+use tokio::io::AsyncReadExt;
+use tokio::io::{AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::{Handle, RuntimeFlavor};
+
+/// Helper trait for lazy writing.
+pub trait WriteSeek: AsyncWrite + AsyncSeek + Unpin + Send {}
+
+/// Blanket implementation for anything viable.
+impl<T: AsyncWrite + AsyncSeek + Unpin + Send> WriteSeek for T {}
+
+/// Writer trait for HffDesc.  Each write returns how much content the
+/// written structure shares between entries.
+///
+/// Compression, deduplication and reading `Reader` and `Range` sources
+/// are synchronous.  On a multi threaded runtime they run through
+/// `block_in_place` so other tasks move to another worker, on a current
+/// thread runtime they block the runtime while they run.
+#[async_trait]
+pub trait Writer {
+    /// Write to a stream.
+    async fn write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc> + Send,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<DedupStats>;
+
+    /// Write to a stream but finalize chunk lazilly during the write.
+    /// This requires a stream with both Write and Seek capabilities.
+    async fn lazy_write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc> + Send,
+        writer: &mut dyn WriteSeek,
+    ) -> Result<DedupStats>;
+}
+
+#[async_trait]
+impl<'a> Writer for HffDesc<'a> {
+    async fn write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc> + Send,
+        writer: &mut (dyn AsyncWrite + Unpin + Send),
+    ) -> Result<DedupStats> {
+        let offset_to_blob = self.offset_to_blob() as u64;
+        let checksum = self.checksum();
+        let parallel = self.parallel();
+        let dedup = self.dedup();
+        let (mut tables, mut chunks, mut data) = self.finish();
+
+        // Store identical content once and prepare all the data in the
+        // data array so we have offsets, length and checksums.
+        let map = blocking(|| data.compress_and_dedup(parallel, dedup))?;
+        let offset_len = blocking(|| data.prepare_with(checksum, parallel))?;
+
+        // Update the table metadata length/offset/checksum and chunk length/offset/checksum.
+        let stats = HffDesc::update_data(
+            &mut tables,
+            &mut chunks,
+            offset_to_blob,
+            checksum,
+            &DataArray::expand(&map, &offset_len),
+        );
+
+        // Nothing is written until the content has been laid out, so
+        // content which can't be fails without partial output.
+        let header = Header::new(
+            id_type,
+            content_type.into(),
+            tables.len() as u32,
+            chunks.len() as u32,
+        );
+        writer.write_all(header.to_bytes::<E>()?.as_slice()).await?;
+
+        // And write the content+data blob.
+        writer.write_all(tables.to_bytes::<E>()?.as_slice()).await?;
+        writer.write_all(chunks.to_bytes::<E>()?.as_slice()).await?;
+        // Files are read again while writing so they are checked against
+        // what was prepared.
+        write_data_array(data, checksum, Some(&offset_len), writer).await?;
+        writer.flush().await?;
+
+        Ok(stats)
+    }
+
+    async fn lazy_write<E: ByteOrder>(
+        self,
+        id_type: IdType,
+        content_type: impl Into<Ecc> + Send,
+        writer: &mut dyn WriteSeek,
+    ) -> Result<DedupStats> {
+        let array_size = self.arrays_size();
+        let offset_to_blob = self.offset_to_blob() as u64;
+        let checksum = self.checksum();
+        let parallel = self.parallel();
+        let dedup = self.dedup();
+        let (mut tables, mut chunks, mut data) = self.finish();
+
+        let header = Header::new(
+            id_type,
+            content_type.into(),
+            tables.len() as u32,
+            chunks.len() as u32,
+        );
+        writer.write_all(header.to_bytes::<E>()?.as_slice()).await?;
+
+        // Write zero's for the table and chunk array.
+        // Use this rather than skipping in order to avoid any questionable
+        // differences between different backing types.
+        writer.write_all(&vec![0; array_size]).await?;
+
+        // Compress everything up front when running in parallel and store
+        // identical content once, the checksums are computed as the data
+        // is written.
+        let map = blocking(|| data.compress_and_dedup(parallel, dedup))?;
+
+        // Write the data and record the offset/length/checksum information.
+        let offset_len = write_data_array(data, checksum, None, writer).await?;
+
+        // Update the table metadata length/offset/checksum and chunk length/offset/checksum.
+        let stats = HffDesc::update_data(
+            &mut tables,
+            &mut chunks,
+            offset_to_blob,
+            checksum,
+            &DataArray::expand(&map, &offset_len),
+        );
+
+        // Seek back to the tables/chunks.
+        writer
+            .seek(std::io::SeekFrom::Start(Header::SIZE as u64))
+            .await?;
+
+        // And write the tables and chunks.
+        writer.write_all(tables.to_bytes::<E>()?.as_slice()).await?;
+        writer.write_all(chunks.to_bytes::<E>()?.as_slice()).await?;
+        writer.flush().await?;
+
+        Ok(stats)
+    }
+}
+
+/// Write the data to the given stream.
+/// Returns a vector of offset into the writer (starting from 0),
+/// the length of the data written without alignment padding and
+/// the checksum of the data.  Fails if any entry differs from the
+/// expected layout when given.
+async fn write_data_array(
+    data_array: DataArray<'_>,
+    checksum: Checksum,
+    expected: Option<&[(u64, u64, u64)]>,
+    writer: &mut (dyn AsyncWrite + Unpin + Send),
+) -> Result<Vec<(u64, u64, u64)>> {
+    let mut offset_len = vec![];
+    let base = data_array.base();
+    let alignments = data_array.alignments();
+
+    // Track where we are in the writer, starting from zero.
+    let mut offset = 0;
+    for (mut item, alignment) in data_array.into_iter().zip(alignments) {
+        // Pad up to the alignment of the entry.
+        let aligned = DataArray::align(base, offset, alignment);
+        writer
+            .write_all(&vec![0; (aligned - offset) as usize])
+            .await?;
+        offset = aligned;
+
+        // Prepare each item.
+        // This is only for compressed data (at this time) to perform
+        // the compression.  The compression itself is synchronous so
+        // it is performed in memory before being written.
+        if !item.is_stream() {
+            blocking(|| item.prepare())?;
+        }
+
+        // Write in the appropriate manner, computing the checksum as
+        // the data passes through.
+        let path = item.file_path().map(|path| path.to_path_buf());
+        let mut hasher = checksum.hasher();
+        let length = match item {
+            DataSource::File(f, expected) => {
+                // Hand the file over to tokio so the content is streamed
+                // without blocking the runtime.
+                let mut f = tokio::fs::File::from_std(f).take(expected);
+                let mut buffer = vec![0; 64 * 1024];
+                let mut length = 0;
+                loop {
+                    let count = f.read(&mut buffer).await?;
+                    if count == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..count]);
+                    writer.write_all(&buffer[..count]).await?;
+                    length += count as u64;
+                }
+                if length != expected {
+                    return Err(DataSource::content_changed(None));
+                }
+                length
+            }
+            DataSource::Path(path, expected) => {
+                // Only this file is open while it is being written.
+                let mut f = tokio::fs::File::from_std(DataSource::open_path(&path, expected)?)
+                    .take(expected);
+                let mut buffer = vec![0; 64 * 1024];
+                let mut length = 0;
+                loop {
+                    let count = f.read(&mut buffer).await?;
+                    if count == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..count]);
+                    writer.write_all(&buffer[..count]).await?;
+                    length += count as u64;
+                }
+                if length != expected {
+                    return Err(DataSource::path_changed(&path));
+                }
+                length
+            }
+            DataSource::Owned(data) => {
+                hasher.update(&data);
+                writer.write_all(data.as_slice()).await?;
+                data.len() as u64
+            }
+            DataSource::Ref(data) => {
+                hasher.update(data);
+                writer.write_all(data).await?;
+                data.len() as u64
+            }
+            DataSource::Reader(mut stream) => {
+                // The stream is synchronous, it is read a block at a time
+                // between the writes.
+                let mut buffer = vec![0; 64 * 1024];
+                let mut length = 0;
+                loop {
+                    let count = blocking(|| std::io::Read::read(&mut stream, &mut buffer))?;
+                    if count == 0 {
+                        break;
+                    }
+                    hasher.update(&buffer[..count]);
+                    writer.write_all(&buffer[..count]).await?;
+                    length += count as u64;
+                }
+                length
+            }
+            DataSource::Range(range) => {
+                // The source is read a block at a time so no reader is
+                // held across the writes.
+                let mut length = 0;
+                while length < range.len() {
+                    let block = blocking(|| range.read_block(length, 64 * 1024))?;
+                    if block.is_empty() {
+                        break;
+                    }
+                    hasher.update(&block);
+                    writer.write_all(&block).await?;
+                    length += block.len() as u64;
+                }
+                length
+            }
+            #[cfg(feature = "compression")]
+            DataSource::Compressed(_, _, _, _, data) => {
+                let data = data.ok_or_else(|| {
+                    hff_core::Error::Invalid("Internal error dealing with compressed data.".into())
+                })?;
+                hasher.update(&data);
+                writer.write_all(data.as_slice()).await?;
+                data.len() as u64
+            }
+        };
+
+        // Record the offset, length and checksum.
+        let entry = (offset, length, hasher.finish());
+        if expected.is_some_and(|expected| expected.get(offset_len.len()) != Some(&entry)) {
+            return Err(DataSource::content_changed(path.as_deref()));
+        }
+        offset_len.push(entry);
+        offset += length;
+    }
+
+    // The data always ends on a 16 byte boundary.
+    let padding = DataArray::align(base, offset, Chunk::ALIGNMENT) - offset;
+    writer.write_all(&vec![0; padding as usize]).await?;
+
+    Ok(offset_len)
+}
+
+/// Run synchronous work on a multi threaded runtime without holding up
+/// the other tasks of the worker.  A current thread runtime has no other
+/// worker to hand them to so the work runs in place.
+fn blocking<R>(work: impl FnOnce() -> R) -> R {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(work)
+        }
+        _ => work(),
+    }
+}
@@ -0,0 +1,43 @@
+use super::Result;
+use clap::Args;
+use hff_core::Error;
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+/// Rewrite an hff keeping only the referenced content.
+#[derive(Debug, Args)]
+pub struct Compact {
+    /// The input hff to compact.
+    pub input: PathBuf,
+    /// The output file to write the compacted hff to.
+    pub output: PathBuf,
+}
+
+impl Compact {
+    /// Execute the subcommand.
+    pub fn execute(self) -> Result<()> {
+        // The input is read while the output is written so they can't be
+        // the same file.
+        if self.output.exists() && self.input.canonicalize()? == self.output.canonicalize()? {
+            return Err(Error::Invalid(
+                "The input and output must be different files.".into(),
+            ));
+        }
+
+        let input = File::open(&self.input)?;
+        let mut output = BufWriter::new(File::create(&self.output)?);
+        let compaction = hff_std::compact(input, &mut output)?;
+        output.flush()?;
+
+        println!(
+            "Reclaimed {} bytes: {} -> {}",
+            compaction.reclaimed(),
+            compaction.original,
+            compaction.compacted
+        );
+        Ok(())
+    }
+}
@@ -55,7 +55,7 @@ impl Dump {
         } else {
             Err(Error::Invalid(format!(
                 "Invalid input: {}",
-                self.input.display().to_string()
+                self.input.display()
             )))
         }
     }
@@ -157,14 +157,9 @@ impl Dump {
     fn indent(&self, depth: usize) -> String {
         if self.indent {
             if self.indent_size * depth < self.max_indent {
-                std::iter::repeat(' ')
-                    .take(self.indent_size * depth)
-                    .collect::<String>()
+                " ".repeat(self.indent_size * depth)
             } else {
-                std::iter::repeat(' ')
-                    .take((self.indent_size * self.max_indent) - 3)
-                    .collect::<String>()
-                    + "-> "
+                " ".repeat((self.indent_size * self.max_indent) - 3) + "-> "
             }
         } else {
             String::new()
@@ -1,6 +1,9 @@
 use super::Result;
 use clap::Subcommand;
 
+mod compact;
+pub use compact::*;
+
 mod dump;
 pub use dump::*;
 
@@ -16,6 +19,8 @@ pub use structure::*;
 /// Commands supported.
 #[derive(Debug, Subcommand)]
 pub enum Commands {
+    /// The compact command.
+    Compact(#[command(subcommand)] Compact),
     /// The dump command.
     Dump(#[command(subcommand)] Dump),
     /// The pack command.
@@ -28,6 +33,7 @@ impl Commands {
     /// Execute the subcommand.
     pub fn execute(self) -> Result<()> {
         match self {
+            Self::Compact(compact) => compact.execute(),
             Self::Dump(dump) => dump.execute(),
             Self::Pack(pack) => pack.execute(),
             Self::Unpack(unpack) => unpack.execute(),
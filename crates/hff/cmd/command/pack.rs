@@ -1,6 +1,6 @@
 use super::Result;
 use clap::Args;
-use hff_std::{hff, Ecc, IdType, Writer, BE, LE, NE};
+use hff_std::{hff, Codec, Ecc, IdType, Writer, BE, LE, NE};
 use log::trace;
 use normpath::PathExt;
 use std::{fs::File, io::Write, path::PathBuf};
@@ -21,7 +21,7 @@ pub const HFF_LZMA: Ecc = Ecc::new("_LZMA");
 pub struct Pack {
     /// The input path to a file or directory.
     pub input: PathBuf,
-    /// The output file to pack to.
+    /// The output file to pack to, `-` streams to stdout.
     pub output: PathBuf,
 
     /// If we want to be recursive.
@@ -34,6 +34,19 @@ pub struct Pack {
     /// Compression level for the content?
     #[arg(long, short)]
     pub compress: Option<u32>,
+    /// The codec used to compress: xz, zstd or lz4.
+    #[arg(long, default_value = "xz", requires = "compress")]
+    pub codec: Codec,
+    /// Compress the files on all available cores.
+    #[arg(long, short = 'j')]
+    pub parallel: bool,
+    /// Store identical files only once.
+    #[arg(long)]
+    pub dedup: bool,
+    /// Align file content to the given number of bytes, rounded up to a
+    /// power of two of at least 16.
+    #[arg(long)]
+    pub align: Option<u32>,
 
     /// Force big endian mode.
     #[arg(long, conflicts_with = "little_endian")]
@@ -58,21 +71,53 @@ impl Pack {
         let structure = structure.strip_prefix(parent)?;
 
         // Build up the tables for the structure.
-        let root = structure.to_tables::<NE>(parent, |_| self.compress)?;
+        let embedded = structure.open_hff(parent);
+        let root = structure.to_tables::<NE>(parent, embedded.as_ref(), |_| {
+            self.compress.map(|level| (self.codec, level))
+        })?;
 
-        // Create a file to write the content into.
-        let mut output = File::create(&self.output)?;
-        // And write in the selected endian or native.
-        if !(self.little_endian || self.big_endian) {
-            hff([root]).write::<NE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?;
+        let mut content = hff([root])
+            .with_parallel(self.parallel)
+            .with_dedup(self.dedup);
+        if let Some(alignment) = self.align {
+            content = content.with_alignment(alignment)?;
+        }
+
+        // Stdout can't seek so it gets the single pass footer layout.
+        let stats = if self.output.as_os_str() == "-" {
+            let mut output = std::io::stdout().lock();
+            let stats = if self.big_endian {
+                content.stream_write::<BE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
+            } else if self.little_endian {
+                content.stream_write::<LE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
+            } else {
+                content.stream_write::<NE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
+            };
+            output.flush()?;
+            stats
         } else {
-            if self.big_endian {
-                hff([root]).write::<BE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?;
+            // Create a file to write the content into.
+            let mut output = File::create(&self.output)?;
+            // And write in the selected endian or native.
+            let stats = if self.big_endian {
+                content.write::<BE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
             } else if self.little_endian {
-                hff([root]).write::<LE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?;
-            }
+                content.write::<LE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
+            } else {
+                content.write::<NE>(IdType::EccU64, HFF_ARCHIVE, &mut output)?
+            };
+            output.flush()?;
+            stats
+        };
+
+        // Report what deduplication saved, on stderr so it stays out of
+        // streamed output.
+        if self.dedup {
+            eprintln!(
+                "Deduplicated {} of {} entries saving {} bytes.",
+                stats.shared, stats.entries, stats.bytes_saved
+            );
         }
-        output.flush()?;
 
         Ok(())
     }
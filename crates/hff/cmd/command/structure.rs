@@ -26,9 +26,9 @@ impl Structure {
             let file_type = metadata.file_type();
 
             if file_type.is_file() {
-                Ok(Self::File(path.into()))
+                Ok(Self::File(path))
             } else if file_type.is_dir() {
-                Ok(Self::scan_directory(path.into(), recursive)?)
+                Ok(Self::scan_directory(path, recursive)?)
             } else {
                 Err(Error::Invalid(format!("Invalid root: {:?}", path)))
             }
@@ -63,36 +63,46 @@ impl Structure {
     /// Scan the given directory for files and child directories.
     fn scan_directory(path: PathBuf, recursive: bool) -> Result<Self> {
         let mut result = vec![];
-        let mut reader = read_dir(&path)?;
+        let reader = read_dir(&path)?;
 
-        while let Some(entry) = reader.next() {
+        for entry in reader {
             match entry {
                 Ok(entry) => {
                     let metadata = entry.metadata()?;
                     if metadata.file_type().is_file() {
                         result.push(Self::File(entry.path().file_name().unwrap().into()));
-                    } else if metadata.file_type().is_dir() {
-                        if recursive {
-                            let path = entry.path();
-                            result.push(Self::scan_directory(path, recursive)?);
-                        }
+                    } else if metadata.file_type().is_dir() && recursive {
+                        let path = entry.path();
+                        result.push(Self::scan_directory(path, recursive)?);
                     }
                 }
                 Err(e) => return Err(e.into()),
             }
         }
 
-        Ok(Self::Directory(path.into(), result))
+        Ok(Self::Directory(path, result))
     }
 
-    /// Convert the structure into a set of tables.
+    /// Open the input as an hff if it is a single hff file so it can be
+    /// embedded.
+    pub fn open_hff(&self, root: &Path) -> Option<Hff<StdReader>> {
+        match self {
+            Self::File(file) => hff_std::open(File::open(root.join(file)).ok()?).ok(),
+            Self::Directory(_, _) => None,
+        }
+    }
+
+    /// Convert the structure into a set of tables.  An hff opened with
+    /// `open_hff` is embedded, its content is streamed from it as the
+    /// archive is written.
     pub fn to_tables<'a, E: ByteOrder>(
         self,
         root: &Path,
-        compression: impl Fn(&Path) -> Option<u32>,
+        embedded: Option<&'a Hff<StdReader>>,
+        compression: impl Fn(&Path) -> Option<(Codec, u32)>,
     ) -> Result<TableBuilder<'a>> {
         match self {
-            Self::File(file) => archive_single_file::<E>(root, file, &compression),
+            Self::File(file) => archive_single_file::<E>(root, file, embedded, &compression),
             Self::Directory(path, children) => {
                 archive_directory::<E>(root, path, children, &compression)
             }
@@ -104,7 +114,7 @@ fn archive_directory<'a, E: ByteOrder>(
     root: &Path,
     path: PathBuf,
     structure: Vec<Structure>,
-    compression: &impl Fn(&Path) -> Option<u32>,
+    compression: &impl Fn(&Path) -> Option<(Codec, u32)>,
 ) -> Result<TableBuilder<'a>> {
     // Build children tables and chunks.
     let (tables, chunks, files, hierarchy) =
@@ -117,11 +127,12 @@ fn archive_directory<'a, E: ByteOrder>(
         .chunks(chunks))
 }
 
+#[allow(clippy::type_complexity)]
 fn archive_level<'a, E: ByteOrder>(
     root: &Path,
     path: PathBuf,
     children: Vec<Structure>,
-    compression: &impl Fn(&Path) -> Option<u32>,
+    compression: &impl Fn(&Path) -> Option<(Codec, u32)>,
 ) -> Result<(
     Vec<TableBuilder<'a>>,
     Vec<ChunkDesc<'a>>,
@@ -137,21 +148,16 @@ fn archive_level<'a, E: ByteOrder>(
     for child in children {
         match child {
             Structure::File(file) => {
-                let file: std::path::PathBuf = file.into();
                 files.push(file.display().to_string());
 
                 let path = root.join(path.join(&file));
-                chunks.push(file_to_chunk(compression, path.into())?);
+                chunks.push(file_to_chunk(compression, path)?);
             }
             Structure::Directory(p, c) => {
                 let root = root.join(&path);
                 let (t, c, f, h) = archive_level::<E>(&root, p.clone(), c, compression)?;
                 hierarchy.push(Hierarchical::new(p.display().to_string(), f, h));
-                tables.push(
-                    table((super::HFF_DIR, Ecc::INVALID))
-                        .children(t.into_iter())
-                        .chunks(c.into_iter()),
-                )
+                tables.push(table((super::HFF_DIR, Ecc::INVALID)).children(t).chunks(c))
             }
         }
     }
@@ -164,17 +170,16 @@ fn archive_level<'a, E: ByteOrder>(
 fn archive_single_file<'a, E: ByteOrder>(
     root: &Path,
     file: PathBuf,
-    compression: &impl Fn(&Path) -> Option<u32>,
+    embedded: Option<&'a Hff<StdReader>>,
+    compression: &impl Fn(&Path) -> Option<(Codec, u32)>,
 ) -> Result<TableBuilder<'a>> {
     // Build the path to the file.
     let file_path = root.join(&file);
 
-    // Attempt to open the file as an hff first.
-    match hff_std::open(File::open(&file_path)?) {
-        Ok(hff) => Ok(hff_to_table::<E>(file, hff)?),
-        Err(_) => {
+    match embedded {
+        Some(hff) => Ok(hff_to_table::<E>(file, hff)?),
+        None => {
             // The file is not an hff, so just pack it into a chunk.
-            let file_path: std::path::PathBuf = file_path.into();
             let chunk = file_to_chunk(compression, file_path)?;
             Ok(table((super::HFF_FILE, Ecc::INVALID))
                 .chunks([chunk])
@@ -187,11 +192,14 @@ fn archive_single_file<'a, E: ByteOrder>(
 }
 
 /// Given an hff file, convert it into a decomposed table.
-fn hff_to_table<'a, E: ByteOrder>(file: PathBuf, hff: Hff<StdReader>) -> Result<TableBuilder<'a>> {
-    // Convert content for embedding.
+fn hff_to_table<'a, E: ByteOrder>(
+    file: PathBuf,
+    hff: &'a Hff<StdReader>,
+) -> Result<TableBuilder<'a>> {
+    // Graft the content for embedding.
     let mut children = vec![];
     for t in hff.tables() {
-        children.push(resolve_table(&hff, t)?);
+        children.push(graft(&t)?);
     }
 
     // This new table contains the content of the prior hff.
@@ -199,42 +207,19 @@ fn hff_to_table<'a, E: ByteOrder>(file: PathBuf, hff: Hff<StdReader>) -> Result<
     // this new table has no chunks, only the original children tables.
     let result = table((super::HFF_EMBEDDED, hff.content_type()))
         .metadata(Hierarchical::new(file.display().to_string(), vec![], vec![]).to_bytes::<E>()?)?
-        .children(children.into_iter());
+        .children(children);
 
     Ok(result)
 }
 
-fn resolve_table<'a, 'b>(
-    hff: &'a Hff<StdReader>,
-    t: TableView<'a, StdReader>,
-) -> Result<TableBuilder<'b>> {
-    // Get all the chunks.
-    let mut chunks = vec![];
-    for c in t.chunks() {
-        chunks.push(chunk(c.identifier(), hff.get(&c)?)?);
-    }
-
-    // Recursively resolve all child tables.
-    let mut children = vec![];
-    for t in t.iter() {
-        children.push(resolve_table(hff, t)?);
-    }
-
-    // And build the whole thing as a table in the archive.
-    Ok(table(t.identifier())
-        .chunks(chunks.into_iter())
-        .children(children.into_iter()))
-}
-
 /// Convert the given file into a chunk without decomposition.
-fn file_to_chunk<'a, F: Fn(&Path) -> Option<u32>>(
+fn file_to_chunk<'a, F: Fn(&Path) -> Option<(Codec, u32)>>(
     compression: &F,
     file_path: std::path::PathBuf,
 ) -> Result<ChunkDesc<'a>> {
-    let compression = compression(file_path.as_path().into());
-    let chunk = if let Some(compression) = compression {
-        let size = file_path.metadata()?.len();
-        chunk((super::HFF_FILE, size), (compression, file_path))?
+    let compression = compression(file_path.as_path());
+    let chunk = if let Some((codec, level)) = compression {
+        chunk((super::HFF_FILE, Ecc::INVALID), (codec, level, file_path))?
     } else {
         chunk((super::HFF_FILE, Ecc::INVALID), file_path)?
     };
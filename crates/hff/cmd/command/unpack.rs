@@ -1,18 +1,18 @@
 use super::Result;
 use clap::Args;
 use hff_core::{read::TableIter, utilities::Hierarchical, Error};
-use hff_std::{open, ContentInfo, Hff, StdReader, TableView};
+use hff_std::{open, Hff, StdReader, TableView};
 use log::trace;
 use std::{
     fs::{create_dir_all, File},
-    io::Read,
+    io::{Cursor, Read},
     path::{Path, PathBuf},
 };
 
 /// Unpack an archive hff to the given location or file.
 #[derive(Debug, Args)]
 pub struct Unpack {
-    /// The input hff to unpack.
+    /// The input hff to unpack, `-` reads from stdin.
     pub input: PathBuf,
     /// The output location for the command.
     pub output: PathBuf,
@@ -21,8 +21,15 @@ pub struct Unpack {
 impl Unpack {
     /// Execute the subcommand.
     pub fn execute(self) -> Result<()> {
-        // Open and validate the hff file.
-        let hff = open(File::open(&self.input)?)?;
+        // Open and validate the hff file.  Stdin can't seek so it is
+        // read into memory first.
+        let hff = if self.input.as_os_str() == "-" {
+            let mut buffer = vec![];
+            std::io::stdin().lock().read_to_end(&mut buffer)?;
+            open(Cursor::new(buffer))?
+        } else {
+            open(File::open(&self.input)?)?
+        };
         if hff.content_type() == super::HFF_ARCHIVE {
             // Figure out if the archive is a single file
             // or a directory.
@@ -68,10 +75,10 @@ impl Unpack {
                 let table = hff.tables().next().unwrap();
                 let hierarchy = Hierarchical::from_bytes(hff.get(&table)?.as_slice())?;
                 self.write_chunks(&hff, &self.output, hierarchy.content(), &table)?;
-                self.unpack_level(0, &self.output, &hff, table.iter(), hierarchy.children())?;
+                self.unpack_level(&self.output, &hff, table.iter(), hierarchy.children())?;
                 Ok(())
             } else {
-                Err(Error::Invalid(format!("Invalid structure.")))
+                Err(Error::Invalid("Invalid structure.".to_string()))
             }
         } else {
             Err(Error::Invalid(format!("Output invalid: {:?}", self.output)))
@@ -81,7 +88,6 @@ impl Unpack {
     /// Unpack a single table.
     fn unpack_level(
         &self,
-        depth: usize,
         location: &Path,
         hff: &Hff<StdReader>,
         level: TableIter<'_, StdReader>,
@@ -101,7 +107,7 @@ impl Unpack {
             self.write_chunks(hff, &child_dir, names, &table)?;
 
             // Recurse into the child.
-            self.unpack_level(depth + 1, &child_dir, hff, table.iter(), desc.children())?;
+            self.unpack_level(&child_dir, hff, table.iter(), desc.children())?;
         }
         Ok(())
     }
@@ -114,25 +120,14 @@ impl Unpack {
         names: &[String],
         table: &TableView<'_, StdReader>,
     ) -> Result<()> {
-        for (index, chunk) in table.chunks().into_iter().enumerate() {
-            let (primary, uncompressed_size) = chunk.identifier().as_eccu64();
+        for (index, chunk) in table.chunks().enumerate() {
+            let (primary, _) = chunk.identifier().as_eccu64();
             if primary == super::HFF_FILE {
-                if uncompressed_size > 0 {
-                    // Read from the chunk.
-                    let reader: &mut dyn Read = &mut *hff.read(&chunk)?;
-                    let mut buffer = vec![0; chunk.len() as usize];
-                    reader.read_exact(&mut buffer)?;
-
-                    // Create a decompressor for the chunk.
-                    let buffer = hff_std::decompress(&mut buffer.as_mut_slice())?;
-
-                    let mut output = File::create(path.join(&names[index]))?;
-                    std::io::copy(&mut buffer.as_slice(), &mut output)?;
-                } else {
-                    let reader: &mut dyn Read = &mut *hff.read(&chunk)?;
-                    let mut output = File::create(path.join(&names[index]))?;
-                    std::io::copy(reader, &mut output)?;
-                }
+                // Stream the chunk through its codec so large files are
+                // unpacked in constant memory.
+                let mut reader = hff.decompressed_reader(&chunk)?;
+                let mut output = File::create(path.join(&names[index]))?;
+                std::io::copy(&mut reader, &mut output)?;
             } else {
                 unimplemented!()
             }
@@ -59,25 +59,64 @@
 //! # In Progress
 //! - [x] Depth first iterator through tables.
 //! - [x] More metadata/chunk data source types.  Most things which can be turned into
-//! Vec<u8> exist now, read trait for anything which can be immediately pulled in at
-//! runtime and finally std::path::{Path, PathBuf} to pull data from a file.
+//!   Vec<u8> exist now, read trait for anything which can be immediately pulled in at
+//!   runtime and finally std::path::{Path, PathBuf} to pull data from a file.
 //! - [x] Yet more metadata/chunk data source types.
-//! Compression is done and uses lzma due to the desired performance versus compression.
-//! Pass in a tuple with: (level, any valid data source) where level is 0-9.
+//!   Compression is done and uses lzma due to the desired performance versus compression.
+//!   Pass in a tuple with: (level, any valid data source) where level is 0-9.
+//! - [x] Pluggable compression codecs.  Zstd and lz4 are available behind the `zstd` and
+//!   `lz4` features, pass a tuple with: (codec, level, any valid data source).  The codec
+//!   is recorded per chunk and metadata entry so readers know how to decompress.
+//! - [x] Self describing compression.  The uncompressed length is stored alongside the
+//!   codec so `hff.get_decompressed(&chunk)` works for any identifier scheme.
+//! - [x] Streaming decompression.  `hff.decompressed_reader(&chunk)` decodes directly from
+//!   the underlying reader so huge chunks can be processed in constant memory.
 //! - [x] Utility types for metadata.  For instance a simple key=value string map and a
-//! simple array of strings.
+//!   simple array of strings.
 //! - [x] Change the table builder to allow multiple tables at the 'root' level.
-//! Currently the builder expects a single outer table to contain all others.  This
-//! is a holdover from a prior format structure which was removed.
+//!   Currently the builder expects a single outer table to contain all others.  This
+//!   is a holdover from a prior format structure which was removed.
 //! - [x] After fixing the table builder, implement the lazy header variation so compressed
-//! chunks do not have to be stored in memory prior to writing.
+//!   chunks do not have to be stored in memory prior to writing.
+//! - [x] Incremental writer.  `HffWriter` opens, fills and closes tables one at a time so
+//!   content can be written as it is produced without building the whole tree first.  It
+//!   writes the footer layout so the output is only ever appended to.
+//! - [x] Single pass writing.  `stream_write` uses the footer layout, placing the table and
+//!   chunk arrays after the data, so pipes and sockets can be written without Seek.
+//! - [x] Editing.  `HffEditor` adds, replaces and removes tables, chunks and metadata of an
+//!   existing hff and writes the result copying untouched content straight from the source.
+//! - [x] Deduplication.  `with_dedup(true)` stores identical content once and
+//!   the writers and `hff.dedup_stats()` report the bytes saved.
+//! - [x] Alignment.  `with_alignment(n)?` aligns all content and `chunk(..)?.aligned(n)?` a
+//!   single chunk, the alignment is recorded per chunk and metadata entry for readers.
+//! - [x] Serde content.  With the `serde` feature `metadata_serde(&value, encoding)` and
+//!   `chunk_serde(id, &value, encoding)` store any `Serialize` type as bincode, CBOR, Json
+//!   or MessagePack.  The encoding is recorded so `table.metadata_as::<T>()` and
+//!   `chunk.decode::<T>()` read it back.
+//! - [x] Derive.  With the `derive` feature `#[derive(HffTable)]` maps a struct onto a table,
+//!   fields become metadata, chunks or child tables with `#[hff(...)]` attributes and
+//!   `to_table()` / `from_table(&view)` convert in both directions.
+//! - [x] Path lookup.  `hff.find(&[a, b, c])` and `hff.glob("Root:*/Mesh:LOD0")` return the
+//!   matching tables and chunks, `hff.path_index()` precomputes every path for repeated lookups.
+//! - [x] Grafting.  `graft(&table)` copies a table with its children from an open hff, the
+//!   content is streamed from the source as stored when written.
+//! - [x] Streams.  `DataSource::reader(r)` writes any `Read` of unknown length with
+//!   `lazy_write`, `stream_write` or `HffWriter`, the length is recorded once written.
+//! - [x] Lazy files.  Paths become `DataSource::Path` which is only opened while written, so
+//!   packing huge trees stays within the open file limit.
+//! - [x] Typed errors.  Readers and the utilities report `Error::BadMagic`,
+//!   `UnsupportedVersion`, `Truncated { section }`, `OutOfBounds`, `UnknownIdType` and
+//!   friends rather than strings so callers can tell problems apart.
+//! - [x] Compaction.  `compact` and `hff compact` drop unreferenced content from an hff.
 //! - [ ] Remove the development testing and write better and more complete tests.
 //! - [ ] Better examples.
 //! - [x] Async-std implementation of the reader.
-//! - [ ] Async-std implementation of the writer.
+//! - [x] Async-std implementation of the writer.
 //! - [x] Tokio implementation of the reader.
-//! - [ ] Tokio implementation of the writer.
-//! - [ ] Mmap, io_ring and whatever other variations make sense in the long run.
+//! - [x] Tokio implementation of the writer.
+//! - [x] Mmap implementation of the reader.
+//! - [x] Positional (pread) implementation of the reader for lock free concurrent access.
+//! - [ ] io_ring and whatever other variations make sense in the long run.
 #![warn(missing_docs)]
 
 // Core types.
@@ -87,6 +126,13 @@ pub use hff_core::*;
 #[doc(inline)]
 pub use hff_std;
 
+// The derive refers to core through the facade.
+#[doc(hidden)]
+pub use hff_core;
+
+#[cfg(feature = "derive")]
+pub use hff_std::HffTable;
+
 #[cfg(feature = "async-std-rt")]
 #[doc(inline)]
 pub use hff_async_std;
@@ -0,0 +1,8 @@
+#![no_main]
+
+use hff_core::utilities::Hierarchical;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Hierarchical::from_bytes(data);
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Walk the structure every way it can be walked.
+    if let Ok(hff) = hff_std::inspect(&mut &data[..]) {
+        for (_, table) in hff.depth_first() {
+            for child in table.iter() {
+                let _ = child.identifier();
+            }
+            for chunk in table.chunks() {
+                let _ = (chunk.identifier(), chunk.size());
+            }
+        }
+        for table in hff.tables() {
+            let _ = table.iter().count();
+        }
+    }
+});
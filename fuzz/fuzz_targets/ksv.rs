@@ -0,0 +1,8 @@
+#![no_main]
+
+use hff_core::utilities::Ksv;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Ksv::from_bytes(data);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Random access through the seekable reader.
+    if let Ok(hff) = hff_std::open(std::io::Cursor::new(data.to_vec())) {
+        for (_, table) in hff.depth_first() {
+            let _ = hff.get(&table);
+            for chunk in table.chunks() {
+                let _ = hff.get(&chunk);
+                let _ = chunk.verify();
+            }
+        }
+    }
+});
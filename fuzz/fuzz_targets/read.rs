@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Anything which parses must be safe to walk and read.
+    if let Ok(hff) = hff_std::read(&mut &data[..]) {
+        for (_, table) in hff.depth_first() {
+            let _ = hff.read(&table);
+            for chunk in table.chunks() {
+                let _ = hff.read(&chunk);
+            }
+        }
+        let _ = hff.verify();
+    }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use hff_core::utilities::StringVec;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = StringVec::from_bytes(data);
+});